@@ -0,0 +1,206 @@
+//! Derive macros for [argonic](https://docs.rs/argonic). Use them through the re-exports in
+//! argonic itself rather than depending on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, spanned::Spanned, Data, DeriveInput, Expr, Fields, Ident, LitStr, Variant,
+};
+
+/// Implements `From<T> for ResponseError` for an error enum, so handlers can return domain
+/// errors and have them turned into the right code and message.
+///
+/// Every variant needs an `#[rpc_error(code = ..., message = "...")]` attribute. The message can
+/// refer to the variant's fields like a format string, by name or by position for tuple variants.
+/// One field per variant can be marked `#[rpc_error(data)]` to send it as the error's data.
+///
+/// ```ignore
+/// #[derive(Debug, RpcError)]
+/// enum AccountError {
+///     #[rpc_error(code = -32010, message = "quota exceeded")]
+///     QuotaExceeded,
+///     #[rpc_error(code = 404, message = "no account named {0}")]
+///     NotFound(String),
+///     #[rpc_error(code = 422, message = "invalid {field}")]
+///     Invalid {
+///         field: String,
+///         #[rpc_error(data)]
+///         reasons: Vec<String>,
+///     },
+/// }
+/// ```
+#[proc_macro_derive(RpcError, attributes(rpc_error))]
+pub fn derive_rpc_error(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_rpc_error(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_rpc_error(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "RpcError can only be derived for enums",
+        ));
+    };
+
+    let arms = data
+        .variants
+        .iter()
+        .map(|variant| expand_variant(&input.ident, variant))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    // An enum without variants can't be constructed, but the match still has to be exhaustive.
+    let body = if arms.is_empty() {
+        quote!(match error {})
+    } else {
+        quote!(match error { #(#arms)* })
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::core::convert::From<#name #ty_generics>
+            for ::argonic::response::ResponseError #where_clause
+        {
+            #[allow(unused_variables)]
+            fn from(error: #name #ty_generics) -> Self {
+                #body
+            }
+        }
+    })
+}
+
+fn expand_variant(name: &Ident, variant: &Variant) -> syn::Result<TokenStream2> {
+    let mut code: Option<Expr> = None;
+    let mut message: Option<LitStr> = None;
+
+    for attr in &variant.attrs {
+        if !attr.path().is_ident("rpc_error") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("code") {
+                code = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("message") {
+                message = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `code` or `message`"))
+            }
+        })?;
+    }
+
+    let missing = || {
+        syn::Error::new(
+            variant.ident.span(),
+            "expected #[rpc_error(code = ..., message = \"...\")] on every variant",
+        )
+    };
+    let code = code.ok_or_else(missing)?;
+    let message = message.ok_or_else(missing)?;
+
+    // Fields are bound to their own names, or `_0`, `_1`, ... for tuple variants, so the message
+    // can refer to them with inline format arguments.
+    let bindings: Vec<_> = variant
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match &field.ident {
+            Some(ident) => ident.clone(),
+            None => format_ident!("_{}", index),
+        })
+        .collect();
+
+    let mut data = None;
+    for (field, binding) in variant.fields.iter().zip(&bindings) {
+        if !has_data_attr(field)? {
+            continue;
+        }
+        if data.is_some() {
+            return Err(syn::Error::new(
+                field.span(),
+                "only one field can be marked #[rpc_error(data)]",
+            ));
+        }
+        data = Some(binding);
+    }
+
+    let ident = &variant.ident;
+    // `Self` is ResponseError inside the generated impl, so the enum has to be named.
+    let pattern = match &variant.fields {
+        Fields::Named(_) => quote!(#name::#ident { #(#bindings),* }),
+        Fields::Unnamed(_) => quote!(#name::#ident(#(#bindings),*)),
+        Fields::Unit => quote!(#name::#ident),
+    };
+
+    let message = LitStr::new(&positional_to_named(&message.value()), message.span());
+    let error = quote! {
+        ::argonic::response::ResponseError::new(
+            <::argonic::response::ErrorCode as ::core::convert::From<i64>>::from(#code),
+            ::std::format!(#message),
+        )
+    };
+    // No `let`s here, so that a field can't be shadowed before the message gets to it.
+    let error = match data {
+        Some(binding) => quote!(::argonic::__private::with_data(#error, &#binding)),
+        None => error,
+    };
+
+    Ok(quote!(#pattern => #error,))
+}
+
+fn has_data_attr(field: &syn::Field) -> syn::Result<bool> {
+    let mut data = false;
+    for attr in &field.attrs {
+        if !attr.path().is_ident("rpc_error") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("data") {
+                data = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `data`"))
+            }
+        })?;
+    }
+    Ok(data)
+}
+
+/// Rewrites positional arguments like `{0}` and `{1:?}` to the `_0` and `_1` bindings of tuple
+/// fields, leaving escaped braces and everything else alone.
+fn positional_to_named(message: &str) -> String {
+    let mut output = String::with_capacity(message.len());
+    let mut chars = message.chars().peekable();
+    while let Some(c) = chars.next() {
+        output.push(c);
+        if c != '{' {
+            continue;
+        }
+        if chars.peek() == Some(&'{') {
+            output.push(chars.next().unwrap());
+        } else if chars.peek().is_some_and(char::is_ascii_digit) {
+            output.push('_');
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrite_positional_arguments() {
+        assert_eq!("{_0} and {_1:?}", positional_to_named("{0} and {1:?}"));
+    }
+
+    #[test]
+    fn leave_named_arguments_and_escapes_alone() {
+        assert_eq!("{name} {{0}} {}", positional_to_named("{name} {{0}} {}"));
+    }
+}
@@ -0,0 +1,703 @@
+//! A synchronous client for tools that don't want to pull in an async runtime.
+//!
+//! The client makes one call at a time over any pair of [`Read`]/[`Write`] streams, such as a TCP
+//! socket or a child process's stdout and stdin, framed by a [`MessageCodec`].
+
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    process::{ChildStdin, ChildStdout},
+};
+
+use serde::{de::DeserializeOwned, ser::SerializeStruct, Serialize};
+use serde_json::{value::RawValue, Value};
+
+use crate::{
+    notification::Notification,
+    request::{Request, RequestId},
+    response::{ResponseError, ResponseResult},
+    transport::{
+        codec::{CodecError, MessageCodec, NewlineCodec},
+        Message,
+    },
+};
+
+pub struct Client<R, W, C = NewlineCodec> {
+    reader: R,
+    writer: W,
+    codec: C,
+    read_buffer: Vec<u8>,
+    write_buffer: Vec<u8>,
+    json_buffer: Vec<u8>,
+    next_id: u64,
+}
+
+impl Client<TcpStream, TcpStream> {
+    /// Connects to a server that expects one message per line over TCP.
+    pub fn connect_tcp(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        Ok(Self::new(stream.try_clone()?, stream, NewlineCodec::new()))
+    }
+}
+
+impl Client<ChildStdout, ChildStdin> {
+    /// Talks to a server running as a child process, which expects one message per line on its
+    /// stdin and writes them to its stdout.
+    pub fn stdio(stdout: ChildStdout, stdin: ChildStdin) -> Self {
+        Self::new(stdout, stdin, NewlineCodec::new())
+    }
+}
+
+impl<R, W, C> Client<R, W, C>
+where
+    R: Read,
+    W: Write,
+    C: MessageCodec,
+{
+    pub fn new(reader: R, writer: W, codec: C) -> Self {
+        Self {
+            reader,
+            writer,
+            codec,
+            read_buffer: Vec::new(),
+            write_buffer: Vec::new(),
+            json_buffer: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Calls `method` and waits for its result. `params` must serialize to an array or object,
+    /// or to `null` to leave them out.
+    ///
+    /// Notifications and requests the server sends while we wait are discarded, since a blocking
+    /// client has nobody to hand them to, as are messages that fail to decode, unless they look
+    /// like the response to this call.
+    pub fn call<P, T>(&mut self, method: &str, params: P) -> Result<T, Error>
+    where
+        P: Serialize,
+        T: DeserializeOwned,
+    {
+        let params = serialize_params(params)?;
+        self.call_with_params(method, params)
+    }
+
+    /// Like [`call`](Self::call), but with params that were serialized ahead of time, for polling
+    /// loops that make the same call over and over.
+    ///
+    /// The request is framed with [`MessageCodec::encode_json`], so it comes out the same as it
+    /// would from `call`. Codecs with non-default serialize options have to re-serialize it to
+    /// get there, which gives up most of the savings.
+    pub fn call_prepared<T>(&mut self, method: &str, params: &Params) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let id = self.next_id();
+        // The params are copied into the frame as they are, rather than going through a Request.
+        self.json_buffer.clear();
+        serde_json::to_writer(
+            &mut self.json_buffer,
+            &PreparedRequest {
+                method,
+                params: params.0.as_deref(),
+                id: &id,
+            },
+        )
+        .map_err(Error::Serialize)?;
+        self.write_buffer.clear();
+        self.codec
+            .encode_json(&self.json_buffer, &mut self.write_buffer)
+            .map_err(Error::Codec)?;
+        self.flush()?;
+
+        self.receive_result(id)
+    }
+
+    fn call_with_params<T>(&mut self, method: &str, params: Option<Value>) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let id = self.next_id();
+        self.send(&Message::Request(Request::new(method, params, id.clone())))?;
+        self.receive_result(id)
+    }
+
+    fn next_id(&mut self) -> RequestId {
+        let id = RequestId::Number(self.next_id.into());
+        self.next_id += 1;
+        id
+    }
+
+    /// Whether `id` is one of ours from a call before the current one.
+    fn is_earlier_call(&self, id: &RequestId) -> bool {
+        match id {
+            RequestId::Number(number) => number
+                .as_u64()
+                .is_some_and(|number| number + 1 < self.next_id),
+            RequestId::String(_) | RequestId::Null => false,
+        }
+    }
+
+    fn receive_result<T>(&mut self, id: RequestId) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        loop {
+            let message = match self.receive() {
+                Ok(message) => message,
+                // A frame that doesn't decode only fails the call if it was meant to answer it.
+                Err(Error::Codec(CodecError::Decode(err))) if err.response_id() != Some(&id) => {
+                    tracing::warn!("skipping message that failed to decode: {}", err);
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+            match message {
+                Message::Response(response) if *response.id() == id => {
+                    return match response.result() {
+                        ResponseResult::Ok(result) => {
+                            crate::de::from_value(result).map_err(Error::Deserialize)
+                        }
+                        ResponseResult::Err(error) => Err(Error::Rpc(error.clone())),
+                    };
+                }
+                // The server answers with a null id when it couldn't read the id of the request,
+                // and with only one call in flight, that request must have been ours.
+                Message::Response(response) if *response.id() == RequestId::Null => {
+                    return match response.result() {
+                        ResponseResult::Err(error) => Err(Error::Rpc(error.clone())),
+                        ResponseResult::Ok(_) => Err(Error::UnexpectedResponse(RequestId::Null)),
+                    };
+                }
+                // A late response to an earlier call that gave up waiting, by failing to read or
+                // decode, say.
+                Message::Response(response) if self.is_earlier_call(response.id()) => {
+                    tracing::debug!("skipping response to abandoned call {}", response.id());
+                }
+                Message::Response(response) => {
+                    return Err(Error::UnexpectedResponse(response.id().clone()))
+                }
+                // We never send batches, so a batch of responses can't be for us either.
+                Message::Request(_)
+                | Message::Notification(_)
+                | Message::BatchRequest(_)
+                | Message::BatchResponse(_) => {}
+            }
+        }
+    }
+
+    pub fn notify<P>(&mut self, method: &str, params: P) -> Result<(), Error>
+    where
+        P: Serialize,
+    {
+        let params = serialize_params(params)?;
+        self.send(&Message::Notification(Notification::new(method, params)))
+    }
+
+    pub fn into_inner(self) -> (R, W) {
+        (self.reader, self.writer)
+    }
+
+    fn send(&mut self, message: &Message) -> Result<(), Error> {
+        self.write_buffer.clear();
+        self.codec
+            .encode(message, &mut self.write_buffer)
+            .map_err(Error::Codec)?;
+        self.flush()
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.writer
+            .write_all(&self.write_buffer)
+            .and_then(|()| self.writer.flush())
+            .map_err(Error::Io)
+    }
+
+    fn receive(&mut self) -> Result<Message, Error> {
+        let mut chunk = [0; 4096];
+        loop {
+            if let Some(message) = self
+                .codec
+                .decode(&mut self.read_buffer)
+                .map_err(Error::Codec)?
+            {
+                return Ok(message);
+            }
+
+            match self.reader.read(&mut chunk) {
+                Ok(0) => return Err(Error::Closed),
+                Ok(n) => self.read_buffer.extend_from_slice(&chunk[..n]),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(Error::Io(err)),
+            }
+        }
+    }
+}
+
+/// Params serialized once up front, to be passed to [`Client::call_prepared`] as many times as
+/// needed. The serialized JSON is kept as it is and copied into each request.
+#[derive(Debug, Clone)]
+pub struct Params(Option<Box<RawValue>>);
+
+impl Params {
+    /// Serializes `params`, which must serialize to an array, an object or `null`.
+    pub fn new<P: Serialize>(params: P) -> Result<Self, Error> {
+        let params = serde_json::value::to_raw_value(&params).map_err(Error::Serialize)?;
+        // Raw values are compact, so the first byte says what kind of value it is.
+        match params.get().as_bytes().first() {
+            Some(b'n') => Ok(Params(None)),
+            Some(b'[' | b'{') => Ok(Params(Some(params))),
+            _ => Err(Error::InvalidParams),
+        }
+    }
+}
+
+/// A request with prepared params, serialized the same way as [`Request`].
+struct PreparedRequest<'a> {
+    method: &'a str,
+    params: Option<&'a RawValue>,
+    id: &'a RequestId,
+}
+
+impl Serialize for PreparedRequest<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let len = if self.params.is_some() { 4 } else { 3 };
+        let mut state = serializer.serialize_struct("Request", len)?;
+        state.serialize_field("jsonrpc", "2.0")?;
+        state.serialize_field("method", self.method)?;
+        if let Some(params) = self.params {
+            state.serialize_field("params", params)?;
+        }
+        state.serialize_field("id", self.id)?;
+        state.end()
+    }
+}
+
+fn serialize_params<P: Serialize>(params: P) -> Result<Option<Value>, Error> {
+    match serde_json::to_value(params).map_err(Error::Serialize)? {
+        Value::Null => Ok(None),
+        params @ (Value::Array(_) | Value::Object(_)) => Ok(Some(params)),
+        _ => Err(Error::InvalidParams),
+    }
+}
+
+/// Calls a method with params written inline, saving the `json!` and turbofish noise:
+///
+/// ```ignore
+/// let difference = rpc_call!(client, "math.subtract", { minuend: 42, subtrahend: 23 } => i64)?;
+/// let sum = rpc_call!(client, "math.sum", [1, 2, 3] => i64)?;
+/// let version: String = rpc_call!(client, "version")?;
+/// ```
+///
+/// Values are Rust expressions, so nested JSON has to go through `json!` itself. Leave off the
+/// `=> Type` to infer the result type from context instead.
+#[macro_export]
+macro_rules! rpc_call {
+    (@call $client:expr, $method:expr, $params:expr => $result:ty) => {{
+        let result: ::core::result::Result<$result, _> = $client.call($method, $params);
+        result
+    }};
+    (@call $client:expr, $method:expr, $params:expr) => {
+        $client.call($method, $params)
+    };
+    ($client:expr, $method:expr, { $($key:ident : $value:expr),* $(,)? } $(=> $result:ty)?) => {
+        $crate::rpc_call!(
+            @call $client,
+            $method,
+            $crate::__private::serde_json::json!({ $(::core::stringify!($key): $value),* })
+            $(=> $result)?
+        )
+    };
+    ($client:expr, $method:expr, [ $($value:expr),* $(,)? ] $(=> $result:ty)?) => {
+        $crate::rpc_call!(
+            @call $client,
+            $method,
+            $crate::__private::serde_json::json!([ $($value),* ])
+            $(=> $result)?
+        )
+    };
+    ($client:expr, $method:expr $(=> $result:ty)?) => {
+        $crate::rpc_call!(@call $client, $method, () $(=> $result)?)
+    };
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Codec(CodecError),
+    /// The server responded with an error.
+    Rpc(ResponseError),
+    /// The params didn't serialize to an array, object or `null`.
+    InvalidParams,
+    Serialize(serde_json::Error),
+    /// The result couldn't be deserialized into the expected type.
+    Deserialize(serde_json::Error),
+    /// The server responded to a request we didn't send.
+    UnexpectedResponse(RequestId),
+    /// The server closed the connection before responding.
+    Closed,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::Codec(err) => err.fmt(f),
+            Error::Rpc(err) => write!(
+                f,
+                "server returned error {}: {}",
+                i64::from(err.code()),
+                err.message()
+            ),
+            Error::InvalidParams => f.write_str("params must be an array, an object or null"),
+            Error::Serialize(err) => write!(f, "failed to serialize params: {}", err),
+            Error::Deserialize(err) => write!(f, "failed to deserialize result: {}", err),
+            Error::UnexpectedResponse(id) => write!(f, "unexpected response with id {}", id),
+            Error::Closed => f.write_str("connection closed before a response was received"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Codec(err) => Some(err),
+            Error::Serialize(err) | Error::Deserialize(err) => Some(err),
+            Error::Rpc(_) | Error::InvalidParams | Error::UnexpectedResponse(_) | Error::Closed => {
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::ErrorCode;
+    use serde_json::json;
+    use std::{
+        io::{BufRead, BufReader},
+        net::TcpListener,
+        thread,
+    };
+
+    fn client(responses: &str) -> Client<&[u8], Vec<u8>> {
+        Client::new(responses.as_bytes(), Vec::new(), NewlineCodec::new())
+    }
+
+    #[test]
+    fn call() {
+        let mut client = client(concat!(
+            r#"{"jsonrpc":"2.0","method":"progress","params":[50]}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","result":19,"id":1}"#,
+            "\n",
+        ));
+
+        let result: i64 = client.call("subtract", (42, 23)).unwrap();
+        assert_eq!(19, result);
+
+        let (_, written) = client.into_inner();
+        assert_eq!(
+            concat!(
+                r#"{"jsonrpc":"2.0","method":"subtract","params":[42,23],"id":1}"#,
+                "\n"
+            ),
+            String::from_utf8(written).unwrap()
+        );
+    }
+
+    #[test]
+    fn call_error() {
+        let mut client = client(concat!(
+            r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":1}"#,
+            "\n",
+        ));
+
+        match client.call::<_, Value>("foobar", ()) {
+            Err(Error::Rpc(error)) => assert_eq!(ErrorCode::MethodNotFound, error.code()),
+            other => panic!("expected Rpc error, got {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "error-paths")]
+    #[test]
+    fn report_path_of_result_error() {
+        let mut client = client(concat!(
+            r#"{"jsonrpc":"2.0","result":{"items":[1,"two"]},"id":1}"#,
+            "\n",
+        ));
+
+        match client.call::<_, std::collections::HashMap<String, Vec<i64>>>("list", ()) {
+            Err(Error::Deserialize(err)) => {
+                assert!(err.to_string().starts_with("items[1]: "), "{}", err)
+            }
+            other => panic!("expected Deserialize error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reject_scalar_params() {
+        assert!(matches!(
+            client("").call::<_, Value>("subtract", 42),
+            Err(Error::InvalidParams)
+        ));
+    }
+
+    #[test]
+    fn connection_closed() {
+        assert!(matches!(
+            client("").call::<_, Value>("subtract", [42, 23]),
+            Err(Error::Closed)
+        ));
+    }
+
+    #[test]
+    fn unexpected_response() {
+        let mut client = client(concat!(r#"{"jsonrpc":"2.0","result":19,"id":7}"#, "\n"));
+        assert!(matches!(
+            client.call::<_, Value>("subtract", [42, 23]),
+            Err(Error::UnexpectedResponse(_))
+        ));
+    }
+
+    #[test]
+    fn skip_undecodable_messages() {
+        let mut client = client(concat!(
+            r#"{"jsonrpc":"2.0","method":1,"params":[50]}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","result":19,"id":1}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","result":-19,"id":2}"#,
+            "\n",
+        ));
+
+        assert_eq!(19, client.call::<_, i64>("subtract", (42, 23)).unwrap());
+        assert_eq!(-19, client.call::<_, i64>("subtract", (23, 42)).unwrap());
+    }
+
+    #[test]
+    fn fail_on_undecodable_response_to_call() {
+        let mut client = client(concat!(
+            r#"{"jsonrpc":"2.0","result":19,"id":1,"error":{}}"#,
+            "\n"
+        ));
+        assert!(matches!(
+            client.call::<_, i64>("subtract", (42, 23)),
+            Err(Error::Codec(CodecError::Decode(_)))
+        ));
+    }
+
+    #[test]
+    fn skip_responses_to_abandoned_calls() {
+        /// Fails the first read, then reads from `data`.
+        struct FailOnce<'a> {
+            failed: bool,
+            data: &'a [u8],
+        }
+
+        impl Read for FailOnce<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if !std::mem::replace(&mut self.failed, true) {
+                    return Err(io::Error::other("connection reset"));
+                }
+                self.data.read(buf)
+            }
+        }
+
+        let reader = FailOnce {
+            failed: false,
+            data: concat!(
+                r#"{"jsonrpc":"2.0","result":19,"id":1}"#,
+                "\n",
+                r#"{"jsonrpc":"2.0","result":-19,"id":2}"#,
+                "\n",
+            )
+            .as_bytes(),
+        };
+        let mut client = Client::new(reader, Vec::new(), NewlineCodec::new());
+
+        assert!(matches!(
+            client.call::<_, i64>("subtract", (42, 23)),
+            Err(Error::Io(_))
+        ));
+        assert_eq!(-19, client.call::<_, i64>("subtract", (23, 42)).unwrap());
+    }
+
+    #[test]
+    fn null_id_error_answers_pending_call() {
+        let mut client = client(concat!(
+            r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid Request"},"id":null}"#,
+            "\n",
+        ));
+
+        match client.call::<_, Value>("subtract", [42, 23]) {
+            Err(Error::Rpc(error)) => {
+                assert_eq!(ErrorCode::InvalidRequest, error.code());
+                assert_eq!("Invalid Request", error.message());
+            }
+            other => panic!("expected Rpc error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn display_unexpected_response() {
+        assert_eq!(
+            "unexpected response with id \"a\"",
+            Error::UnexpectedResponse(RequestId::String("a".to_string())).to_string()
+        );
+    }
+
+    #[test]
+    fn notify() {
+        let mut client = client("");
+        client.notify("update", json!({"value": 1})).unwrap();
+
+        let (_, written) = client.into_inner();
+        assert_eq!(
+            concat!(
+                r#"{"jsonrpc":"2.0","method":"update","params":{"value":1}}"#,
+                "\n"
+            ),
+            String::from_utf8(written).unwrap()
+        );
+    }
+
+    #[test]
+    fn call_prepared() {
+        // Counts how many times it gets serialized.
+        struct Counted(std::cell::Cell<usize>);
+
+        impl Serialize for Counted {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.0.set(self.0.get() + 1);
+                (42, 23).serialize(serializer)
+            }
+        }
+
+        let mut client = client(concat!(
+            r#"{"jsonrpc":"2.0","result":19,"id":1}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","result":19,"id":2}"#,
+            "\n",
+        ));
+
+        let counted = Counted(std::cell::Cell::new(0));
+        let params = Params::new(&counted).unwrap();
+        for _ in 0..2 {
+            let result: i64 = client.call_prepared("subtract", &params).unwrap();
+            assert_eq!(19, result);
+        }
+        assert_eq!(1, counted.0.get());
+        assert_eq!(
+            concat!(
+                r#"{"jsonrpc":"2.0","method":"subtract","params":[42,23],"id":1}"#,
+                "\n",
+                r#"{"jsonrpc":"2.0","method":"subtract","params":[42,23],"id":2}"#,
+                "\n",
+            ),
+            written(client)
+        );
+    }
+
+    #[test]
+    fn call_prepared_with_serialize_options() {
+        let responses = concat!(r#"{"jsonrpc":"2.0","result":19,"id":1}"#, "\n");
+        let codec = || {
+            NewlineCodec::new().with_serialize_options(
+                crate::transport::SerializeOptions::compact().with_sorted_keys(true),
+            )
+        };
+
+        let mut client = Client::new(responses.as_bytes(), Vec::new(), codec());
+        client.call::<_, i64>("subtract", (42, 23)).unwrap();
+        let called = client.into_inner().1;
+
+        let mut client = Client::new(responses.as_bytes(), Vec::new(), codec());
+        let params = Params::new((42, 23)).unwrap();
+        client.call_prepared::<i64>("subtract", &params).unwrap();
+        assert_eq!(called, client.into_inner().1);
+        assert!(called.starts_with(br#"{"id":1,"#));
+    }
+
+    #[test]
+    fn call_prepared_without_params() {
+        let mut client = client(concat!(r#"{"jsonrpc":"2.0","result":"1.0","id":1}"#, "\n"));
+        let params = Params::new(()).unwrap();
+        let result: String = client.call_prepared("version", &params).unwrap();
+        assert_eq!("1.0", result);
+        assert_eq!(
+            concat!(r#"{"jsonrpc":"2.0","method":"version","id":1}"#, "\n"),
+            written(client)
+        );
+    }
+
+    #[test]
+    fn reject_scalar_prepared_params() {
+        assert!(matches!(Params::new("subtract"), Err(Error::InvalidParams)));
+    }
+
+    fn written(client: Client<&[u8], Vec<u8>>) -> String {
+        String::from_utf8(client.into_inner().1).unwrap()
+    }
+
+    #[test]
+    fn rpc_call_with_named_params() {
+        let mut client = client(concat!(r#"{"jsonrpc":"2.0","result":19,"id":1}"#, "\n"));
+        let subtrahend = 23;
+        let result = crate::rpc_call!(client, "math.subtract", { minuend: 42, subtrahend: subtrahend } => i64);
+        assert_eq!(19, result.unwrap());
+        assert_eq!(
+            concat!(
+                r#"{"jsonrpc":"2.0","method":"math.subtract","params":{"minuend":42,"subtrahend":23},"id":1}"#,
+                "\n"
+            ),
+            written(client)
+        );
+    }
+
+    #[test]
+    fn rpc_call_with_positional_params() {
+        let mut client = client(concat!(r#"{"jsonrpc":"2.0","result":6,"id":1}"#, "\n"));
+        let result = crate::rpc_call!(client, "math.sum", [1, 2, 1 + 2] => i64);
+        assert_eq!(6, result.unwrap());
+        assert!(written(client).contains(r#""params":[1,2,3]"#));
+    }
+
+    #[test]
+    fn rpc_call_without_params() {
+        let mut client = client(concat!(r#"{"jsonrpc":"2.0","result":"1.0","id":1}"#, "\n"));
+        let result: Result<String, Error> = crate::rpc_call!(client, "version");
+        assert_eq!("1.0", result.unwrap());
+        assert!(!written(client).contains("params"));
+    }
+
+    #[test]
+    fn call_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let request: Request = serde_json::from_str(&line).unwrap();
+            let response = crate::response::Response::ok(
+                request.id().clone(),
+                json!(request.params().unwrap()[0].as_i64().unwrap() * 2),
+            );
+            serde_json::to_writer(&mut writer, &response).unwrap();
+            writer.write_all(b"\n").unwrap();
+        });
+
+        let mut client = Client::connect_tcp(addr).unwrap();
+        let result: i64 = client.call("double", [21]).unwrap();
+        assert_eq!(42, result);
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,194 @@
+//! Canonical JSON text, following the JSON Canonicalization Scheme (RFC 8785).
+//!
+//! Anything that needs to treat two JSON values as "the same" (cache keys, idempotency keys,
+//! signatures) should key on [`canonicalize`] so they all agree on what that means.
+
+use serde_json::{Number, Value};
+
+/// Renders `value` as canonical JSON text: no insignificant whitespace, object keys sorted by
+/// their UTF-16 code units, and numbers formatted the way ECMAScript formats them.
+///
+/// Like RFC 8785, numbers are treated as IEEE 754 doubles, so integers outside of ±2^53 lose
+/// precision exactly as they would in a JavaScript implementation.
+pub fn canonicalize(value: &Value) -> String {
+    let mut output = String::new();
+    write_value(&mut output, value);
+    output
+}
+
+fn write_value(output: &mut String, value: &Value) {
+    match value {
+        Value::Null => output.push_str("null"),
+        Value::Bool(true) => output.push_str("true"),
+        Value::Bool(false) => output.push_str("false"),
+        Value::Number(number) => write_number(output, number),
+        Value::String(string) => write_string(output, string),
+        Value::Array(values) => {
+            output.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    output.push(',');
+                }
+                write_value(output, value);
+            }
+            output.push(']');
+        }
+        Value::Object(fields) => {
+            let mut fields: Vec<_> = fields.iter().collect();
+            fields.sort_unstable_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+
+            output.push('{');
+            for (i, (key, value)) in fields.into_iter().enumerate() {
+                if i > 0 {
+                    output.push(',');
+                }
+                write_string(output, key);
+                output.push(':');
+                write_value(output, value);
+            }
+            output.push('}');
+        }
+    }
+}
+
+fn write_string(output: &mut String, string: &str) {
+    // serde_json escapes exactly the characters JCS requires (quote, backslash and control
+    // characters, using the short forms where they exist and lowercase hex otherwise).
+    output.push_str(&serde_json::to_string(string).expect("strings always serialize"));
+}
+
+fn write_number(output: &mut String, number: &Number) {
+    match number.as_f64() {
+        Some(number) => write_f64(output, number),
+        // Only reachable with serde_json's arbitrary_precision feature, in which case the
+        // original text is the best we can do.
+        None => output.push_str(&number.to_string()),
+    }
+}
+
+/// Formats a double using the ECMAScript Number::toString algorithm.
+fn write_f64(output: &mut String, number: f64) {
+    // This also covers -0, which ECMAScript renders as 0.
+    if number == 0.0 {
+        output.push('0');
+        return;
+    }
+    if number < 0.0 {
+        output.push('-');
+    }
+
+    // Rust's exponential formatting gives us the shortest digit string that round-trips, which
+    // is the same one ECMAScript picks. We just need to lay it out differently.
+    let formatted = format!("{:e}", number.abs());
+    let (mantissa, exponent) = formatted
+        .split_once('e')
+        .expect("exponential formatting always includes an exponent");
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let exponent: i32 = exponent.parse().expect("exponent is always an integer");
+
+    // In ECMAScript terms, the value is digits * 10^(n - k).
+    let k = digits.len() as i32;
+    let n = exponent + 1;
+
+    if k <= n && n <= 21 {
+        output.push_str(&digits);
+        output.extend(std::iter::repeat_n('0', (n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        let (integer, fraction) = digits.split_at(n as usize);
+        output.push_str(integer);
+        output.push('.');
+        output.push_str(fraction);
+    } else if -6 < n && n <= 0 {
+        output.push_str("0.");
+        output.extend(std::iter::repeat_n('0', -n as usize));
+        output.push_str(&digits);
+    } else {
+        let (first, rest) = digits.split_at(1);
+        output.push_str(first);
+        if !rest.is_empty() {
+            output.push('.');
+            output.push_str(rest);
+        }
+        output.push('e');
+        output.push(if n > 0 { '+' } else { '-' });
+        output.push_str(&(n - 1).abs().to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn number(value: f64) -> String {
+        canonicalize(&Value::Number(Number::from_f64(value).unwrap()))
+    }
+
+    #[test]
+    fn rfc_8785_example() {
+        // The RFC uses 333333333.33333329 here, but serde_json's parser isn't correctly rounded
+        // unless the float_roundtrip feature is enabled, so we use the shortest form instead.
+        let value: Value = serde_json::from_str(
+            r#"{
+                "numbers": [333333333.3333333, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+                "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+                "literals": [null, true, false]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#,
+            canonicalize(&value)
+        );
+    }
+
+    #[test]
+    fn sorts_keys_by_utf16_code_units() {
+        // U+FB33 sorts before U+1F600 in UTF-8 but after it in UTF-16.
+        let value = json!({"\u{1F600}": 1, "\u{FB33}": 2, "a": 3});
+        assert_eq!(
+            "{\"a\":3,\"\u{1F600}\":1,\"\u{FB33}\":2}",
+            canonicalize(&value)
+        );
+    }
+
+    #[test]
+    fn nested_structures() {
+        let value = json!({"b": [{"d": 1, "c": 2}], "a": {}});
+        assert_eq!(r#"{"a":{},"b":[{"c":2,"d":1}]}"#, canonicalize(&value));
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!("0", canonicalize(&json!(0)));
+        assert_eq!("42", canonicalize(&json!(42)));
+        assert_eq!("-42", canonicalize(&json!(-42)));
+        assert_eq!(
+            "9007199254740992",
+            canonicalize(&json!(9007199254740992u64))
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!("0", number(-0.0));
+        assert_eq!("4.5", number(4.5));
+        assert_eq!("-4.5", number(-4.5));
+        assert_eq!("0.000001", number(0.000001));
+        assert_eq!("1e-7", number(1e-7));
+        assert_eq!("1.5e-7", number(1.5e-7));
+        assert_eq!("100000000000000000000", number(1e20));
+        assert_eq!("1e+21", number(1e21));
+        assert_eq!("1.7976931348623157e+308", number(f64::MAX));
+        assert_eq!("5e-324", number(5e-324));
+        assert_eq!("333333333.3333333", number(333333333.3333333));
+    }
+
+    #[test]
+    fn equivalent_values_canonicalize_identically() {
+        let a: Value = serde_json::from_str(r#"{ "x": 1.0, "y": [1e2] }"#).unwrap();
+        let b: Value = serde_json::from_str(r#"{"y":[100],"x":1}"#).unwrap();
+        assert_eq!(canonicalize(&a), canonicalize(&b));
+    }
+}
@@ -0,0 +1,34 @@
+//! Deserialization shared by the decoder and the clients.
+
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Deserializes a `T` out of `value`. With the `error-paths` feature, errors say where in the
+/// value they happened, as in `params.filters[2].range.min: invalid type: string "1", expected
+/// i64`.
+pub(crate) fn from_value<'de, T>(value: &'de Value) -> Result<T, serde_json::Error>
+where
+    T: Deserialize<'de>,
+{
+    #[cfg(feature = "error-paths")]
+    {
+        use serde_path_to_error::Segment;
+
+        serde_path_to_error::deserialize(value).map_err(|err| {
+            if err
+                .path()
+                .iter()
+                .all(|segment| matches!(segment, Segment::Unknown))
+            {
+                // Nothing to add, so keep the original error and its classification.
+                err.into_inner()
+            } else {
+                serde::de::Error::custom(err)
+            }
+        })
+    }
+    #[cfg(not(feature = "error-paths"))]
+    {
+        T::deserialize(value)
+    }
+}
@@ -0,0 +1,248 @@
+//! A place for applications to declare their own error codes, so that two errors can't quietly
+//! end up sharing a code and errors read back off the wire can be shown with a readable name.
+
+use std::{collections::BTreeMap, fmt};
+
+use crate::response::{ErrorCode, ResponseError};
+
+/// The codes from -32768 to -32000 are reserved by the spec. Of those, only -32099 to -32000 are
+/// left for implementations to define.
+const RESERVED: std::ops::RangeInclusive<i64> = -32768..=-32100;
+
+#[derive(Debug, Default)]
+pub struct ErrorRegistry {
+    definitions: BTreeMap<i64, ErrorDefinition>,
+}
+
+impl ErrorRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares an error code. Fails if the code or the name is already taken, or if the code is
+    /// one the spec reserves for its own errors.
+    pub fn define(
+        &mut self,
+        code: i64,
+        name: impl Into<String>,
+    ) -> Result<&mut ErrorDefinition, DefineError> {
+        let name = name.into();
+        if RESERVED.contains(&code) {
+            return Err(DefineError::Reserved(code));
+        }
+        if let Some(existing) = self.definitions.get(&code) {
+            return Err(DefineError::DuplicateCode {
+                code,
+                existing: existing.name.clone(),
+            });
+        }
+        if let Some(existing) = self.by_name(&name) {
+            return Err(DefineError::DuplicateName {
+                name,
+                existing: existing.code,
+            });
+        }
+
+        Ok(self.definitions.entry(code).or_insert(ErrorDefinition {
+            code,
+            name,
+            description: None,
+        }))
+    }
+
+    pub fn get(&self, code: ErrorCode) -> Option<&ErrorDefinition> {
+        self.definitions.get(&i64::from(code))
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&ErrorDefinition> {
+        self.definitions
+            .values()
+            .find(|definition| definition.name == name)
+    }
+
+    /// Every definition, in order of code.
+    pub fn iter(&self) -> impl Iterator<Item = &ErrorDefinition> {
+        self.definitions.values()
+    }
+
+    /// Displays `error` with the name of its code, if it has one, as in
+    /// `QUOTA_EXCEEDED (-32050): quota exceeded`.
+    pub fn display<'a>(&'a self, error: &'a ResponseError) -> DisplayError<'a> {
+        DisplayError {
+            definition: self.get(error.code()),
+            error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorDefinition {
+    code: i64,
+    name: String,
+    description: Option<String>,
+}
+
+impl ErrorDefinition {
+    pub fn with_description(&mut self, description: impl Into<String>) -> &mut Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::from(self.code)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// An error with this code and `message`.
+    pub fn error(&self, message: impl Into<String>) -> ResponseError {
+        ResponseError::new(self.code(), message)
+    }
+}
+
+/// Returned by [`ErrorRegistry::display`].
+#[derive(Debug)]
+pub struct DisplayError<'a> {
+    definition: Option<&'a ErrorDefinition>,
+    error: &'a ResponseError,
+}
+
+impl fmt::Display for DisplayError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let code = i64::from(self.error.code());
+        match self.definition {
+            Some(definition) => write!(f, "{} ({})", definition.name, code)?,
+            None => write!(f, "{}", code)?,
+        }
+        write!(f, ": {}", self.error.message())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DefineError {
+    /// The code is reserved by the JSON-RPC 2.0 spec.
+    Reserved(i64),
+    DuplicateCode {
+        code: i64,
+        existing: String,
+    },
+    DuplicateName {
+        name: String,
+        existing: i64,
+    },
+}
+
+impl fmt::Display for DefineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DefineError::Reserved(code) => {
+                write!(f, "error code {} is reserved by the JSON-RPC spec", code)
+            }
+            DefineError::DuplicateCode { code, existing } => {
+                write!(f, "error code {} is already defined as {}", code, existing)
+            }
+            DefineError::DuplicateName { name, existing } => {
+                write!(
+                    f,
+                    "error name {} is already used by code {}",
+                    name, existing
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for DefineError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn define_error() {
+        let mut registry = ErrorRegistry::new();
+        registry
+            .define(-32050, "QUOTA_EXCEEDED")
+            .unwrap()
+            .with_description("The caller has used up its quota for the day.");
+
+        let definition = registry.get(ErrorCode::from(-32050)).unwrap();
+        assert_eq!("QUOTA_EXCEEDED", definition.name());
+        assert_eq!(
+            Some("The caller has used up its quota for the day."),
+            definition.description()
+        );
+        assert_eq!(Some(definition), registry.by_name("QUOTA_EXCEEDED"));
+    }
+
+    #[test]
+    fn reject_duplicate_code() {
+        let mut registry = ErrorRegistry::new();
+        registry.define(1, "NOT_FOUND").unwrap();
+        assert_eq!(
+            Err(DefineError::DuplicateCode {
+                code: 1,
+                existing: "NOT_FOUND".to_string()
+            }),
+            registry.define(1, "MISSING").map(|_| ())
+        );
+    }
+
+    #[test]
+    fn reject_duplicate_name() {
+        let mut registry = ErrorRegistry::new();
+        registry.define(1, "NOT_FOUND").unwrap();
+        assert_eq!(
+            Err(DefineError::DuplicateName {
+                name: "NOT_FOUND".to_string(),
+                existing: 1
+            }),
+            registry.define(2, "NOT_FOUND").map(|_| ())
+        );
+    }
+
+    #[test]
+    fn reject_reserved_codes() {
+        let mut registry = ErrorRegistry::new();
+        for code in [-32700, -32601, -32100, -32768] {
+            assert_eq!(
+                Err(DefineError::Reserved(code)),
+                registry.define(code, "MINE").map(|_| ())
+            );
+        }
+        for code in [-32099, -32000, -31999, -32769] {
+            registry.define(code, format!("MINE{}", code)).unwrap();
+        }
+    }
+
+    #[test]
+    fn iterate_in_code_order() {
+        let mut registry = ErrorRegistry::new();
+        registry.define(2, "B").unwrap();
+        registry.define(-32001, "A").unwrap();
+        let names: Vec<_> = registry.iter().map(ErrorDefinition::name).collect();
+        assert_eq!(vec!["A", "B"], names);
+    }
+
+    #[test]
+    fn display_with_name() {
+        let mut registry = ErrorRegistry::new();
+        let error = registry
+            .define(-32050, "QUOTA_EXCEEDED")
+            .unwrap()
+            .error("quota exceeded");
+        assert_eq!(
+            "QUOTA_EXCEEDED (-32050): quota exceeded",
+            registry.display(&error).to_string()
+        );
+
+        let error = ResponseError::new(ErrorCode::ApplicationError(7), "unknown");
+        assert_eq!("7: unknown", registry.display(&error).to_string());
+    }
+}
@@ -0,0 +1,127 @@
+//! Types that handlers can take as arguments to get at parts of a request, in the style of axum's
+//! extractors.
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::response::{ErrorCode, ResponseError};
+
+/// Something a handler can take as its argument, built from the request's params.
+pub trait FromParams: Sized {
+    /// Builds the argument, or returns the error to respond with instead of calling the handler.
+    fn from_params(params: Option<Value>) -> Result<Self, ResponseError>;
+}
+
+/// The raw params, exactly as they were sent.
+impl FromParams for Option<Value> {
+    fn from_params(params: Option<Value>) -> Result<Self, ResponseError> {
+        Ok(params)
+    }
+}
+
+/// Deserializes the params into a `T`, responding with `InvalidParams` if they don't fit.
+///
+/// ```ignore
+/// async fn subtract(Params((a, b)): Params<(i64, i64)>) -> Result<i64, ResponseError> {
+///     Ok(a - b)
+/// }
+/// ```
+///
+/// Clients don't agree on how to send no params: some leave them out, others send `null`, `[]`
+/// or `{}`. All four are accepted for any `T` that can be deserialized from one of them, so
+/// `Params<()>` and structs whose fields are all optional work with every client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Params<T>(pub T);
+
+impl<T: DeserializeOwned> FromParams for Params<T> {
+    fn from_params(params: Option<Value>) -> Result<Self, ResponseError> {
+        let params = params.unwrap_or(Value::Null);
+        let error = match crate::de::from_value(&params) {
+            Ok(params) => return Ok(Params(params)),
+            Err(err) => err,
+        };
+
+        let empty = match &params {
+            Value::Null => true,
+            Value::Array(params) => params.is_empty(),
+            Value::Object(params) => params.is_empty(),
+            _ => false,
+        };
+        if empty {
+            let alternatives = [
+                Value::Null,
+                Value::Array(Vec::new()),
+                Value::Object(Map::new()),
+            ];
+            if let Some(params) = alternatives
+                .iter()
+                .find_map(|params| crate::de::from_value(params).ok())
+            {
+                return Ok(Params(params));
+            }
+        }
+
+        // Report the error for the params that were actually sent.
+        Err(
+            ResponseError::new(ErrorCode::InvalidParams, "Invalid params")
+                .with_data(Value::String(error.to_string())),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn deserialize_params() {
+        let Params((a, b)) = Params::<(i64, i64)>::from_params(Some(json!([42, 23]))).unwrap();
+        assert_eq!((42, 23), (a, b));
+
+        let Params(params) =
+            Params::<HashMap<String, i64>>::from_params(Some(json!({"minuend": 42}))).unwrap();
+        assert_eq!(Some(&42), params.get("minuend"));
+    }
+
+    #[test]
+    fn reject_invalid_params() {
+        let error = Params::<(i64, i64)>::from_params(Some(json!(["42", 23]))).unwrap_err();
+        assert_eq!(ErrorCode::InvalidParams, error.code());
+        assert_eq!("Invalid params", error.message());
+        assert!(error
+            .data()
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .contains("invalid type"));
+    }
+
+    #[test]
+    fn reject_missing_params() {
+        let error = Params::<(i64, i64)>::from_params(None).unwrap_err();
+        assert_eq!(ErrorCode::InvalidParams, error.code());
+    }
+
+    #[test]
+    fn accept_every_kind_of_empty_params() {
+        for params in [None, Some(json!(null)), Some(json!([])), Some(json!({}))] {
+            assert!(Params::<()>::from_params(params.clone()).is_ok());
+            assert!(Params::<Option<Vec<i64>>>::from_params(params.clone()).is_ok());
+            assert_eq!(
+                Params(HashMap::new()),
+                Params::<HashMap<String, i64>>::from_params(params).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn pass_raw_params_through() {
+        assert_eq!(
+            Some(json!([1, 2])),
+            Option::<Value>::from_params(Some(json!([1, 2]))).unwrap()
+        );
+        assert_eq!(None, Option::<Value>::from_params(None).unwrap());
+    }
+}
@@ -2,9 +2,36 @@
 
 //! Argonic is an axum-inspired framework for JSON-RPC 2.0 services.
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod canon;
+mod de;
+pub mod error_registry;
+pub mod extract;
 pub mod method;
 pub mod notification;
 pub mod request;
 pub mod response;
+pub mod serde_helpers;
 pub mod server;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod transport;
+
+#[doc(hidden)]
+pub mod __private {
+    //! Support for code generated by argonic's macros. Not public API.
+
+    pub use serde_json;
+
+    use crate::response::ResponseError;
+
+    /// Attaches `data` to `error`, leaving it off if it fails to serialize so the error itself
+    /// isn't lost.
+    pub fn with_data<T: serde::Serialize>(error: ResponseError, data: &T) -> ResponseError {
+        match serde_json::to_value(data) {
+            Ok(data) => error.with_data(data),
+            Err(_) => error,
+        }
+    }
+}
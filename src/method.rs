@@ -1,14 +1,106 @@
-use std::future::Future;
+use std::{fmt, future::Future, pin::Pin};
 
-use crate::response::Response;
+use serde::Serialize;
+use serde_json::Value;
 
-pub trait MethodHandler {
-    type Future: Future<Output = Response> + Send + 'static;
+use crate::{extract::FromParams, response::ResponseError};
 
-    fn call(&self) -> Self::Future;
+pub(crate) type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// An async function that can be registered to handle a method with
+/// [`ServerBuilder::method`](crate::server::ServerBuilder::method).
+///
+/// This is implemented for async functions that take either nothing or one argument that
+/// implements [`FromParams`], such as [`Params`](crate::extract::Params), and return something
+/// that implements [`IntoMethodResult`]. `T` is only there to tell those impls apart.
+pub trait MethodHandler<T>: Clone + Send + Sync + 'static {
+    type Future: Future<Output = Result<Value, MethodError>> + Send + 'static;
+
+    fn call(&self, params: Option<Value>) -> Self::Future;
 
     // TODO: Add a layer to the method
     // fn layer(&self) -> Self::Layer;
 }
 
+impl<F, Fut> MethodHandler<()> for F
+where
+    F: Fn() -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future + Send + 'static,
+    Fut::Output: IntoMethodResult,
+{
+    type Future = BoxFuture<Result<Value, MethodError>>;
+
+    fn call(&self, _params: Option<Value>) -> Self::Future {
+        let future = self();
+        Box::pin(async move { future.await.into_method_result() })
+    }
+}
+
+impl<F, Fut, P> MethodHandler<(P,)> for F
+where
+    F: Fn(P) -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future + Send + 'static,
+    Fut::Output: IntoMethodResult,
+    P: FromParams,
+{
+    type Future = BoxFuture<Result<Value, MethodError>>;
+
+    fn call(&self, params: Option<Value>) -> Self::Future {
+        let future = P::from_params(params).map(self);
+        Box::pin(async move { future?.await.into_method_result() })
+    }
+}
+
+/// What a [`MethodHandler`] can return: a result to serialize, or an error to respond with.
+pub trait IntoMethodResult {
+    fn into_method_result(self) -> Result<Value, MethodError>;
+}
+
+impl<T, E> IntoMethodResult for Result<T, E>
+where
+    T: Serialize,
+    E: Into<ResponseError>,
+{
+    fn into_method_result(self) -> Result<Value, MethodError> {
+        let result = self.map_err(|error| MethodError::Response(error.into()))?;
+        serde_json::to_value(result).map_err(MethodError::Serialize)
+    }
+}
+
+/// Why a [`MethodHandler`] didn't produce a result.
+#[derive(Debug)]
+pub enum MethodError {
+    /// The handler returned an error to send back to the client.
+    Response(ResponseError),
+    /// The handler's result couldn't be serialized, such as a map with keys that aren't strings.
+    /// The server responds with an `InternalError` instead.
+    Serialize(serde_json::Error),
+}
+
+impl From<ResponseError> for MethodError {
+    fn from(error: ResponseError) -> Self {
+        MethodError::Response(error)
+    }
+}
+
+impl fmt::Display for MethodError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MethodError::Response(error) => {
+                write!(f, "error {}: {}", i64::from(error.code()), error.message())
+            }
+            MethodError::Serialize(err) => write!(f, "failed to serialize result: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for MethodError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MethodError::Response(_) => None,
+            MethodError::Serialize(err) => Some(err),
+        }
+    }
+}
+
 pub trait MethodService {}
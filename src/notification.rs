@@ -7,12 +7,33 @@ use std::fmt;
 
 use crate::request::JsonRpcVersion;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Notification {
     method: String,
     params: Option<serde_json::Value>,
 }
 
+impl Notification {
+    pub fn new(method: impl Into<String>, params: Option<serde_json::Value>) -> Self {
+        Self {
+            method: method.into(),
+            params,
+        }
+    }
+
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    pub fn params(&self) -> Option<&serde_json::Value> {
+        self.params.as_ref()
+    }
+
+    pub fn into_parts(self) -> (String, Option<serde_json::Value>) {
+        (self.method, self.params)
+    }
+}
+
 impl Serialize for Notification {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
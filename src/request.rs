@@ -4,15 +4,45 @@ use serde::{
     Deserialize, Serialize,
 };
 use serde_json::Number;
-use std::fmt;
+use std::{cmp::Ordering, fmt};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Request {
     method: String,
     params: Option<serde_json::Value>,
     id: RequestId,
 }
 
+impl Request {
+    pub fn new(
+        method: impl Into<String>,
+        params: Option<serde_json::Value>,
+        id: RequestId,
+    ) -> Self {
+        Self {
+            method: method.into(),
+            params,
+            id,
+        }
+    }
+
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    pub fn params(&self) -> Option<&serde_json::Value> {
+        self.params.as_ref()
+    }
+
+    pub fn id(&self) -> &RequestId {
+        &self.id
+    }
+
+    pub fn into_parts(self) -> (String, Option<serde_json::Value>, RequestId) {
+        (self.method, self.params, self.id)
+    }
+}
+
 impl Serialize for Request {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -140,13 +170,95 @@ impl<'de> Deserialize<'de> for Request {
 /// Represents a JSON-RPC 2.0 request ID. The JSON-RPC 2.0 spec
 /// defines that the request ID can be either a number, a string,
 /// or null.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Ids are ordered `Null` first, then numbers by numeric value, then strings lexicographically.
+/// An integer and a float with the same value (`1` and `1.0`) are different ids, and the integer
+/// sorts first.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum RequestId {
     Number(Number),
     String(String),
     Null,
 }
 
+impl Ord for RequestId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (RequestId::Null, RequestId::Null) => Ordering::Equal,
+            (RequestId::Null, _) => Ordering::Less,
+            (_, RequestId::Null) => Ordering::Greater,
+            (RequestId::Number(a), RequestId::Number(b)) => cmp_numbers(a, b),
+            (RequestId::Number(_), RequestId::String(_)) => Ordering::Less,
+            (RequestId::String(_), RequestId::Number(_)) => Ordering::Greater,
+            (RequestId::String(a), RequestId::String(b)) => a.cmp(b),
+        }
+    }
+}
+
+impl PartialOrd for RequestId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compares two numbers exactly, without rounding large integers to floats.
+fn cmp_numbers(a: &Number, b: &Number) -> Ordering {
+    enum Key {
+        Integer(i128),
+        Float(f64),
+    }
+
+    fn key(number: &Number) -> Key {
+        match (number.as_i64(), number.as_u64(), number.as_f64()) {
+            (Some(n), _, _) => Key::Integer(n.into()),
+            (_, Some(n), _) => Key::Integer(n.into()),
+            // serde_json never holds non-finite floats, so NaN isn't a concern here.
+            (_, _, Some(n)) => Key::Float(n),
+            // Only reachable with serde_json's arbitrary_precision feature.
+            (None, None, None) => Key::Float(f64::NAN),
+        }
+    }
+
+    fn cmp_integer_float(integer: i128, float: f64) -> Ordering {
+        let floor = float.floor();
+        // Every i64 and u64 fits well within these bounds, so the cast below is exact.
+        if floor < i128::MIN as f64 {
+            return Ordering::Greater;
+        }
+        if floor >= i128::MAX as f64 {
+            return Ordering::Less;
+        }
+        match integer.cmp(&(floor as i128)) {
+            // Either the float has a fractional part, or they're numerically equal and the
+            // integer sorts first to stay consistent with Eq.
+            Ordering::Equal => Ordering::Less,
+            ordering => ordering,
+        }
+    }
+
+    match (key(a), key(b)) {
+        (Key::Integer(a), Key::Integer(b)) => a.cmp(&b),
+        // Eq and Hash treat 0.0 and -0.0 as the same id, which total_cmp doesn't.
+        (Key::Float(a), Key::Float(b)) if a == b => Ordering::Equal,
+        (Key::Float(a), Key::Float(b)) => a.total_cmp(&b),
+        (Key::Integer(a), Key::Float(b)) => cmp_integer_float(a, b),
+        (Key::Float(a), Key::Integer(b)) => cmp_integer_float(b, a).reverse(),
+    }
+}
+
+/// Displays the id as it appears in JSON, so `"1"` and `1` can be told apart in logs.
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestId::Number(n) => n.fmt(f),
+            RequestId::String(s) => serde_json::to_string(s)
+                .map_err(|_| fmt::Error)
+                .and_then(|s| f.write_str(&s)),
+            RequestId::Null => f.write_str("null"),
+        }
+    }
+}
+
 impl Serialize for RequestId {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -408,4 +520,69 @@ mod tests {
             serde_json::to_value(request).unwrap()
         );
     }
+
+    fn number(n: f64) -> RequestId {
+        RequestId::Number(Number::from_f64(n).unwrap())
+    }
+
+    #[test]
+    fn order_across_types() {
+        let mut ids = vec![
+            RequestId::String("b".to_string()),
+            RequestId::Number(2.into()),
+            RequestId::Null,
+            RequestId::String("a".to_string()),
+            RequestId::Number((-1).into()),
+            number(1.5),
+        ];
+        ids.sort();
+
+        assert_eq!(
+            vec![
+                RequestId::Null,
+                RequestId::Number((-1).into()),
+                number(1.5),
+                RequestId::Number(2.into()),
+                RequestId::String("a".to_string()),
+                RequestId::String("b".to_string()),
+            ],
+            ids
+        );
+    }
+
+    #[test]
+    fn order_integers_and_floats() {
+        assert!(RequestId::Number(1.into()) < number(1.0));
+        assert!(number(1.0) > RequestId::Number(1.into()));
+        assert!(number(0.5) < RequestId::Number(1.into()));
+        assert!(number(-0.5) > RequestId::Number((-1).into()));
+        assert!(RequestId::Number(u64::MAX.into()) > RequestId::Number(i64::MAX.into()));
+        assert!(RequestId::Number(u64::MAX.into()) < number(1e20));
+        // Both of these are 2^53 + 1, which can't be represented exactly as a float.
+        assert!(RequestId::Number(9007199254740993u64.into()) > number(9007199254740992.0));
+    }
+
+    #[test]
+    fn order_signed_zeros_as_equal() {
+        let negative: RequestId = serde_json::from_str("-0.0").unwrap();
+        let positive: RequestId = serde_json::from_str("0.0").unwrap();
+        assert_eq!(negative, positive);
+        assert_eq!(Ordering::Equal, negative.cmp(&positive));
+    }
+
+    #[test]
+    fn ids_as_map_keys() {
+        let mut ids = std::collections::HashSet::new();
+        assert!(ids.insert(RequestId::Number(1.into())));
+        assert!(ids.insert(RequestId::String("1".to_string())));
+        assert!(!ids.insert(RequestId::Number(1.into())));
+    }
+
+    #[test]
+    fn display_id() {
+        assert_eq!("42", RequestId::Number(42.into()).to_string());
+        assert_eq!("1.5", number(1.5).to_string());
+        assert_eq!("\"abc\"", RequestId::String("abc".to_string()).to_string());
+        assert_eq!("null", RequestId::Null.to_string());
+    }
 }
@@ -4,16 +4,41 @@ use serde::{
     Deserialize, Serialize,
 };
 use serde_json::Value;
-use std::fmt;
+use std::{fmt, time::Duration};
 
 use crate::request::{JsonRpcVersion, RequestId};
 
-#[derive(Debug)]
+#[cfg(feature = "macros")]
+pub use argonic_macros::RpcError;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Response {
     id: RequestId,
     result: ResponseResult,
 }
 
+impl Response {
+    pub fn new(id: RequestId, result: ResponseResult) -> Self {
+        Self { id, result }
+    }
+
+    pub fn ok(id: RequestId, result: Value) -> Self {
+        Self::new(id, ResponseResult::Ok(result))
+    }
+
+    pub fn err(id: RequestId, error: ResponseError) -> Self {
+        Self::new(id, ResponseResult::Err(error))
+    }
+
+    pub fn id(&self) -> &RequestId {
+        &self.id
+    }
+
+    pub fn result(&self) -> &ResponseResult {
+        &self.result
+    }
+}
+
 impl Serialize for Response {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -113,19 +138,207 @@ impl<'de> Deserialize<'de> for Response {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ResponseResult {
     Ok(Value),
     Err(ResponseError),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ResponseError {
     code: ErrorCode,
     message: String,
     data: Option<Value>,
 }
 
+impl ResponseError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn data(&self) -> Option<&Value> {
+        self.data.as_ref()
+    }
+
+    /// Attaches a [`RetryHint`] to the error's `data`. The hint's fields are merged into `data`
+    /// if it's already an object; any other existing `data` is replaced.
+    pub fn with_retry_hint(mut self, hint: RetryHint) -> Self {
+        let Value::Object(fields) = hint.to_value() else {
+            unreachable!("retry hints always serialize to an object");
+        };
+        match &mut self.data {
+            Some(Value::Object(data)) => data.extend(fields),
+            data => *data = Some(Value::Object(fields)),
+        }
+        self
+    }
+
+    /// Reads a [`RetryHint`] out of the error's `data`, if the peer attached one.
+    pub fn retry_hint(&self) -> Option<RetryHint> {
+        self.data
+            .as_ref()
+            .and_then(|data| RetryHint::deserialize(data).ok())
+    }
+}
+
+/// The well-known `data` envelope argonic services use to tell callers whether a failed
+/// request is worth retrying:
+///
+/// ```json
+/// { "retryable": true, "retry_after_ms": 500, "category": "overloaded" }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryHint {
+    retryable: bool,
+    retry_after: Option<Duration>,
+    category: Option<String>,
+}
+
+impl RetryHint {
+    pub fn retryable() -> Self {
+        Self {
+            retryable: true,
+            retry_after: None,
+            category: None,
+        }
+    }
+
+    pub fn not_retryable() -> Self {
+        Self {
+            retryable: false,
+            retry_after: None,
+            category: None,
+        }
+    }
+
+    /// The minimum delay before retrying. Sent with millisecond precision.
+    pub fn with_retry_after(mut self, retry_after: Duration) -> Self {
+        self.retry_after = Some(retry_after);
+        self
+    }
+
+    pub fn with_category(mut self, category: impl Into<String>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.retryable
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.retry_after
+    }
+
+    pub fn category(&self) -> Option<&str> {
+        self.category.as_deref()
+    }
+
+    fn to_value(&self) -> Value {
+        let mut fields = serde_json::Map::new();
+        fields.insert("retryable".to_string(), Value::Bool(self.retryable));
+        if let Some(retry_after) = self.retry_after {
+            // Saturate rather than fail, nobody is going to wait 584 million years anyway.
+            let millis = u64::try_from(retry_after.as_millis()).unwrap_or(u64::MAX);
+            fields.insert("retry_after_ms".to_string(), Value::from(millis));
+        }
+        if let Some(category) = &self.category {
+            fields.insert("category".to_string(), Value::String(category.clone()));
+        }
+        Value::Object(fields)
+    }
+}
+
+impl Serialize for RetryHint {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.to_value().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RetryHint {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct RetryHintVisitor;
+
+        impl<'de> Visitor<'de> for RetryHintVisitor {
+            type Value = RetryHint;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a retry hint object")
+            }
+
+            fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
+            where
+                V: de::MapAccess<'de>,
+            {
+                let mut retryable = None;
+                let mut retry_after_ms: Option<Option<u64>> = None;
+                let mut category: Option<Option<String>> = None;
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "retryable" => {
+                            if retryable.is_some() {
+                                return Err(de::Error::duplicate_field("retryable"));
+                            }
+                            retryable = Some(map.next_value()?);
+                        }
+                        "retry_after_ms" => {
+                            if retry_after_ms.is_some() {
+                                return Err(de::Error::duplicate_field("retry_after_ms"));
+                            }
+                            retry_after_ms = Some(map.next_value()?);
+                        }
+                        "category" => {
+                            if category.is_some() {
+                                return Err(de::Error::duplicate_field("category"));
+                            }
+                            category = Some(map.next_value()?);
+                        }
+                        // The hint shares the error's data object with whatever else the
+                        // application wanted to send, so other fields are expected.
+                        _ => {
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                }
+
+                let retryable = retryable.ok_or_else(|| de::Error::missing_field("retryable"))?;
+
+                Ok(RetryHint {
+                    retryable,
+                    retry_after: retry_after_ms.flatten().map(Duration::from_millis),
+                    category: category.flatten(),
+                })
+            }
+        }
+
+        deserializer.deserialize_map(RetryHintVisitor)
+    }
+}
+
 impl Serialize for ResponseError {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -463,4 +676,66 @@ mod test {
 
         assert!(serde_json::from_value::<Response>(json).is_err());
     }
+
+    #[test]
+    fn serialize_retry_hint() {
+        let error = ResponseError::new(ErrorCode::ServerError(-32001), "Server overloaded")
+            .with_retry_hint(
+                RetryHint::retryable()
+                    .with_retry_after(Duration::from_millis(500))
+                    .with_category("overloaded"),
+            );
+
+        assert_eq!(
+            json!({
+                "code": -32001,
+                "message": "Server overloaded",
+                "data": {
+                    "retryable": true,
+                    "retry_after_ms": 500,
+                    "category": "overloaded"
+                }
+            }),
+            serde_json::to_value(error).unwrap()
+        );
+    }
+
+    #[test]
+    fn retry_hint_merges_into_existing_data() {
+        let error = ResponseError::new(ErrorCode::ApplicationError(1), "Quota exceeded")
+            .with_data(json!({"quota": 10}))
+            .with_retry_hint(RetryHint::not_retryable());
+
+        assert_eq!(
+            Some(&json!({"quota": 10, "retryable": false})),
+            error.data()
+        );
+    }
+
+    #[test]
+    fn read_retry_hint() {
+        let json = json!({
+            "code": -32001,
+            "message": "Server overloaded",
+            "data": {
+                "retryable": true,
+                "retry_after_ms": 250,
+                "details": "try again later"
+            }
+        });
+
+        let error: ResponseError = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            Some(RetryHint::retryable().with_retry_after(Duration::from_millis(250))),
+            error.retry_hint()
+        );
+    }
+
+    #[test]
+    fn missing_retry_hint() {
+        let error = ResponseError::new(ErrorCode::InternalError, "Internal error")
+            .with_data(json!({"details": "oops"}));
+
+        assert_eq!(None, error.retry_hint());
+    }
 }
@@ -0,0 +1,593 @@
+//! serde adapters for encodings that come up a lot in JSON-RPC APIs, for use with
+//! `#[serde(with = "...")]` on fields of params and result types.
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct GetBalance {
+//!     #[serde(with = "argonic::serde_helpers::hex_bytes")]
+//!     address: Vec<u8>,
+//!     #[serde(with = "argonic::serde_helpers::base64")]
+//!     signature: Vec<u8>,
+//!     #[serde(with = "argonic::serde_helpers::hex_quantity")]
+//!     block: u64,
+//!     #[serde(with = "argonic::serde_helpers::stringified")]
+//!     amount: u128,
+//!     #[serde(with = "argonic::serde_helpers::rfc3339")]
+//!     as_of: SystemTime,
+//!     #[serde(
+//!         default,
+//!         skip_serializing_if = "Option::is_none",
+//!         with = "argonic::serde_helpers::double_option"
+//!     )]
+//!     memo: Option<Option<String>>,
+//! }
+//! ```
+
+use std::borrow::Cow;
+
+use ::base64::Engine;
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+/// Values written as strings using their `Display` and `FromStr` implementations. Mostly
+/// useful for integers that don't fit in the 53 bits JavaScript clients can represent exactly.
+pub mod stringified {
+    use std::{fmt::Display, str::FromStr};
+
+    use serde::{de, Deserializer, Serializer};
+
+    pub fn serialize<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Display,
+    {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: FromStr,
+        T::Err: Display,
+    {
+        super::borrowed_str(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Bytes as a `0x` prefixed string of lowercase hex digit pairs. The prefix is optional and
+/// uppercase digits are accepted when deserializing.
+pub mod hex_bytes {
+    use serde::{de, Deserializer, Serializer};
+
+    pub fn serialize<S, T>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: AsRef<[u8]> + ?Sized,
+    {
+        let bytes = bytes.as_ref();
+        let mut encoded = String::with_capacity(2 + bytes.len() * 2);
+        encoded.push_str("0x");
+        for byte in bytes {
+            encoded.push(super::HEX_DIGITS[usize::from(byte >> 4)]);
+            encoded.push(super::HEX_DIGITS[usize::from(byte & 0xF)]);
+        }
+        serializer.serialize_str(&encoded)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let encoded = super::borrowed_str(deserializer)?;
+        let digits = super::strip_hex_prefix(&encoded).as_bytes();
+        if !digits.len().is_multiple_of(2) {
+            return Err(de::Error::custom("hex string has an odd number of digits"));
+        }
+        digits
+            .chunks(2)
+            .map(|pair| Ok(super::hex_digit(pair[0])? << 4 | super::hex_digit(pair[1])?))
+            .collect()
+    }
+}
+
+/// Unsigned integers as `0x` prefixed hex strings without leading zeros (`"0x0"`, `"0x1a"`), the
+/// "quantity" encoding used by Ethereum's JSON-RPC API.
+pub mod hex_quantity {
+    use serde::{de, Deserializer, Serializer};
+
+    pub fn serialize<S>(value: &u64, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(&format_args!("{:#x}", value))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let encoded = super::borrowed_str(deserializer)?;
+        let digits = encoded
+            .strip_prefix("0x")
+            .ok_or_else(|| de::Error::custom("hex quantity must start with 0x"))?;
+        // from_str_radix would also accept a leading sign.
+        if !digits.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+            return Err(de::Error::custom(
+                "hex quantity must only contain hex digits",
+            ));
+        }
+        if digits.is_empty() || (digits.len() > 1 && digits.starts_with('0')) {
+            return Err(de::Error::custom(
+                "hex quantity must have at least one digit and no leading zeros",
+            ));
+        }
+        u64::from_str_radix(digits, 16).map_err(de::Error::custom)
+    }
+}
+
+/// Bytes as standard base64 (RFC 4648 section 4) with padding.
+pub mod base64 {
+    use ::base64::engine::general_purpose::STANDARD;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S, T>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: AsRef<[u8]> + ?Sized,
+    {
+        super::serialize_base64(&STANDARD, bytes.as_ref(), serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        super::deserialize_base64(&STANDARD, deserializer)
+    }
+}
+
+/// Bytes as URL and filename safe base64 (RFC 4648 section 5) without padding.
+pub mod base64_url {
+    use ::base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S, T>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: AsRef<[u8]> + ?Sized,
+    {
+        super::serialize_base64(&URL_SAFE_NO_PAD, bytes.as_ref(), serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        super::deserialize_base64(&URL_SAFE_NO_PAD, deserializer)
+    }
+}
+
+/// `SystemTime`s as RFC 3339 timestamps. Serialized in UTC with as many fractional digits as
+/// needed; any offset is accepted when deserializing.
+pub mod rfc3339 {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use serde::{de, ser, Deserializer, Serializer};
+
+    const SECONDS_PER_DAY: i64 = 86_400;
+
+    pub fn serialize<S>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let (seconds, nanos) = match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => (since.as_secs() as i64, since.subsec_nanos()),
+            Err(err) => {
+                let before = err.duration();
+                match before.subsec_nanos() {
+                    0 => (-(before.as_secs() as i64), 0),
+                    nanos => (-(before.as_secs() as i64) - 1, 1_000_000_000 - nanos),
+                }
+            }
+        };
+
+        let (year, month, day) = civil_from_days(seconds.div_euclid(SECONDS_PER_DAY));
+        if !(0..=9999).contains(&year) {
+            return Err(ser::Error::custom(
+                "RFC 3339 timestamps only support years 0000 to 9999",
+            ));
+        }
+        let second_of_day = seconds.rem_euclid(SECONDS_PER_DAY);
+
+        let mut formatted = format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            second_of_day / 3600,
+            second_of_day / 60 % 60,
+            second_of_day % 60
+        );
+        if nanos > 0 {
+            let fraction = format!("{:09}", nanos);
+            formatted.push('.');
+            formatted.push_str(fraction.trim_end_matches('0'));
+        }
+        formatted.push('Z');
+        serializer.serialize_str(&formatted)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<SystemTime, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let encoded = super::borrowed_str(deserializer)?;
+        parse(&encoded).ok_or_else(|| {
+            de::Error::invalid_value(de::Unexpected::Str(&encoded), &"an RFC 3339 timestamp")
+        })
+    }
+
+    fn parse(input: &str) -> Option<SystemTime> {
+        let bytes = input.as_bytes();
+        // Checking this up front means all of the slicing below is on character boundaries.
+        if !input.is_ascii()
+            || bytes.len() < 20
+            || bytes[4] != b'-'
+            || bytes[7] != b'-'
+            || !matches!(bytes[10], b'T' | b't' | b' ')
+            || bytes[13] != b':'
+            || bytes[16] != b':'
+        {
+            return None;
+        }
+
+        let year = number(&input[0..4])?;
+        let month = number(&input[5..7])?;
+        let day = number(&input[8..10])?;
+        let hour = number(&input[11..13])?;
+        let minute = number(&input[14..16])?;
+        let second = number(&input[17..19])?;
+        if !(1..=12).contains(&month)
+            || day < 1
+            || day > days_in_month(year, month)
+            || hour > 23
+            || minute > 59
+            || second > 60
+        {
+            return None;
+        }
+        // Leap seconds can't be represented by SystemTime, so they're folded into the
+        // preceding second.
+        let second = second.min(59);
+
+        let mut rest = &input[19..];
+        let mut nanos = 0;
+        if let Some(fraction) = rest.strip_prefix('.') {
+            let digits = fraction
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(fraction.len());
+            if digits == 0 {
+                return None;
+            }
+            // Anything past nanosecond precision is truncated.
+            let significant = &fraction[..digits.min(9)];
+            nanos = number(significant)? as u32 * 10u32.pow(9 - significant.len() as u32);
+            rest = &fraction[digits..];
+        }
+
+        let offset = match rest.as_bytes() {
+            [b'Z' | b'z'] => 0,
+            [sign @ (b'+' | b'-'), _, _, b':', _, _] => {
+                let hours = number(&rest[1..3])?;
+                let minutes = number(&rest[4..6])?;
+                if hours > 23 || minutes > 59 {
+                    return None;
+                }
+                let offset = hours * 3600 + minutes * 60;
+                if *sign == b'-' {
+                    -offset
+                } else {
+                    offset
+                }
+            }
+            _ => return None,
+        };
+
+        let seconds = days_from_civil(year, month, day) * SECONDS_PER_DAY
+            + hour * 3600
+            + minute * 60
+            + second
+            - offset;
+        let time = if seconds >= 0 {
+            UNIX_EPOCH + Duration::new(seconds as u64, nanos)
+        } else {
+            UNIX_EPOCH - Duration::from_secs(seconds.unsigned_abs())
+                + Duration::from_nanos(nanos.into())
+        };
+        Some(time)
+    }
+
+    fn number(digits: &str) -> Option<i64> {
+        if digits.bytes().all(|b| b.is_ascii_digit()) {
+            digits.parse().ok()
+        } else {
+            None
+        }
+    }
+
+    fn days_in_month(year: i64, month: i64) -> i64 {
+        match month {
+            2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+
+    // These two are Howard Hinnant's days_from_civil and civil_from_days algorithms, which
+    // convert between proleptic Gregorian dates and days since 1970-01-01.
+
+    fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    fn civil_from_days(days: i64) -> (i64, i64, i64) {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        };
+        let year = year_of_era + era * 400;
+        (if month <= 2 { year + 1 } else { year }, month, day)
+    }
+}
+
+/// Distinguishes a field that's absent (`None`) from one that's explicitly `null`
+/// (`Some(None)`), which matters for patch-style params. The field also needs
+/// `#[serde(default, skip_serializing_if = "Option::is_none")]` for absent fields to round trip.
+pub mod double_option {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S, T>(value: &Option<Option<T>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: Serialize,
+    {
+        match value {
+            // Only reachable without skip_serializing_if, in which case null is the closest
+            // we can get.
+            None | Some(None) => serializer.serialize_none(),
+            Some(Some(value)) => value.serialize(serializer),
+        }
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        // serde only calls this when the field is present, absent fields get the default.
+        Option::<T>::deserialize(deserializer).map(Some)
+    }
+}
+
+const HEX_DIGITS: [char; 16] = [
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c', 'd', 'e', 'f',
+];
+
+fn borrowed_str<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Cow<'de, str>, D::Error> {
+    Cow::<'de, str>::deserialize(deserializer)
+}
+
+fn serialize_base64<S: Serializer, E: Engine>(
+    engine: &E,
+    bytes: &[u8],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&engine.encode(bytes))
+}
+
+fn deserialize_base64<'de, D: Deserializer<'de>, E: Engine>(
+    engine: &E,
+    deserializer: D,
+) -> Result<Vec<u8>, D::Error> {
+    let encoded = borrowed_str(deserializer)?;
+    engine.decode(encoded.as_bytes()).map_err(de::Error::custom)
+}
+
+fn strip_hex_prefix(encoded: &str) -> &str {
+    encoded
+        .strip_prefix("0x")
+        .or_else(|| encoded.strip_prefix("0X"))
+        .unwrap_or(encoded)
+}
+
+fn hex_digit<E: de::Error>(digit: u8) -> Result<u8, E> {
+    match digit {
+        b'0'..=b'9' => Ok(digit - b'0'),
+        b'a'..=b'f' => Ok(digit - b'a' + 10),
+        b'A'..=b'F' => Ok(digit - b'A' + 10),
+        _ => Err(E::custom(format!(
+            "invalid hex digit {:?}",
+            char::from(digit)
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, value::Serializer, Value};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn stringified_numbers() {
+        assert_eq!(
+            json!("18446744073709551616"),
+            stringified::serialize(&18446744073709551616u128, Serializer).unwrap()
+        );
+        assert_eq!(
+            18446744073709551616u128,
+            stringified::deserialize(json!("18446744073709551616")).unwrap()
+        );
+        assert!(stringified::deserialize::<_, u64>(json!("12abc")).is_err());
+        assert!(stringified::deserialize::<_, u64>(json!(12)).is_err());
+    }
+
+    #[test]
+    fn hex_bytes() {
+        assert_eq!(
+            json!("0x00ff1a"),
+            hex_bytes::serialize(&[0x00, 0xff, 0x1a], Serializer).unwrap()
+        );
+        assert_eq!(json!("0x"), hex_bytes::serialize(&[], Serializer).unwrap());
+        assert_eq!(
+            vec![0x00, 0xff, 0x1a],
+            hex_bytes::deserialize(json!("0x00FF1a")).unwrap()
+        );
+        assert_eq!(
+            vec![0xde, 0xad],
+            hex_bytes::deserialize(json!("dead")).unwrap()
+        );
+        assert!(hex_bytes::deserialize(json!("0xabc")).is_err());
+        assert!(hex_bytes::deserialize(json!("0xzz")).is_err());
+    }
+
+    #[test]
+    fn hex_quantities() {
+        assert_eq!(
+            json!("0x0"),
+            hex_quantity::serialize(&0, Serializer).unwrap()
+        );
+        assert_eq!(
+            json!("0x41a"),
+            hex_quantity::serialize(&1050, Serializer).unwrap()
+        );
+        assert_eq!(1050, hex_quantity::deserialize(json!("0x41a")).unwrap());
+        assert_eq!(0, hex_quantity::deserialize(json!("0x0")).unwrap());
+        assert!(hex_quantity::deserialize(json!("0x")).is_err());
+        assert!(hex_quantity::deserialize(json!("0x+1a")).is_err());
+        assert!(hex_quantity::deserialize(json!("0x-1")).is_err());
+        assert!(hex_quantity::deserialize(json!("0x0400")).is_err());
+        assert!(hex_quantity::deserialize(json!("ff")).is_err());
+    }
+
+    #[test]
+    fn serialize_rfc3339() {
+        let serialize = |time| rfc3339::serialize(&time, Serializer).unwrap();
+
+        assert_eq!(json!("1970-01-01T00:00:00Z"), serialize(UNIX_EPOCH));
+        assert_eq!(
+            json!("2024-02-29T13:45:30.25Z"),
+            serialize(UNIX_EPOCH + Duration::new(1_709_214_330, 250_000_000))
+        );
+        assert_eq!(
+            json!("1969-12-31T23:59:59.5Z"),
+            serialize(UNIX_EPOCH - Duration::from_millis(500))
+        );
+    }
+
+    #[test]
+    fn deserialize_rfc3339() {
+        let deserialize = |time: &str| rfc3339::deserialize(json!(time)).unwrap();
+
+        assert_eq!(UNIX_EPOCH, deserialize("1970-01-01T00:00:00Z"));
+        assert_eq!(
+            UNIX_EPOCH + Duration::new(1_709_214_330, 250_000_000),
+            deserialize("2024-02-29T13:45:30.25Z")
+        );
+        assert_eq!(
+            UNIX_EPOCH + Duration::new(1_709_214_330, 0),
+            deserialize("2024-02-29T15:15:30+01:30")
+        );
+        assert_eq!(
+            UNIX_EPOCH - Duration::from_millis(500),
+            deserialize("1969-12-31t23:59:59.500000000001z")
+        );
+    }
+
+    #[test]
+    fn reject_invalid_rfc3339() {
+        for time in [
+            "2023-02-29T00:00:00Z",
+            "2024-13-01T00:00:00Z",
+            "2024-01-01T24:00:00Z",
+            "2024-01-01T00:00:61Z",
+            "2024-01-01T00:00:00",
+            "2024-01-01T00:00:00.Z",
+            "2024-01-01T00:00:00+0100",
+            "2024-01-01",
+            "2024-01-01T00:00:0éZ",
+        ] {
+            assert!(
+                rfc3339::deserialize(json!(time)).is_err(),
+                "accepted {}",
+                time
+            );
+        }
+    }
+
+    #[test]
+    fn rfc3339_round_trip() {
+        let time = SystemTime::now();
+        let serialized = rfc3339::serialize(&time, Serializer).unwrap();
+        assert_eq!(time, rfc3339::deserialize(serialized).unwrap());
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!(
+            json!("aGVsbG8/Pz4+"),
+            base64::serialize(b"hello??>>", Serializer).unwrap()
+        );
+        assert_eq!(
+            b"hello??>>".to_vec(),
+            base64::deserialize(json!("aGVsbG8/Pz4+")).unwrap()
+        );
+        assert!(base64::deserialize(json!("not base64!")).is_err());
+        assert!(base64::deserialize(json!([104, 105])).is_err());
+    }
+
+    #[test]
+    fn base64_url() {
+        assert_eq!(
+            json!("aGVsbG8_Pz4-"),
+            base64_url::serialize(b"hello??>>", Serializer).unwrap()
+        );
+        assert_eq!(
+            b"hello??>>".to_vec(),
+            base64_url::deserialize(json!("aGVsbG8_Pz4-")).unwrap()
+        );
+    }
+
+    #[test]
+    fn double_option() {
+        assert_eq!(
+            Value::Null,
+            double_option::serialize(&Some(None::<i32>), Serializer).unwrap()
+        );
+        assert_eq!(
+            json!(1),
+            double_option::serialize(&Some(Some(1)), Serializer).unwrap()
+        );
+        assert_eq!(
+            Some(None::<i32>),
+            double_option::deserialize(Value::Null).unwrap()
+        );
+        assert_eq!(Some(Some(1)), double_option::deserialize(json!(1)).unwrap());
+    }
+}
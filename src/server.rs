@@ -1,18 +1,64 @@
-use crate::method::MethodHandler;
+use std::{collections::HashMap, fmt, sync::Arc};
 
-pub struct ServerBuilder {}
+use serde_json::Value;
+
+use crate::{
+    method::{BoxFuture, MethodError, MethodHandler},
+    notification::Notification,
+    request::Request,
+    response::{ErrorCode, Response, ResponseError},
+    transport::{BatchEntry, Message},
+};
+
+type BoxedHandler =
+    Arc<dyn Fn(Option<Value>) -> BoxFuture<Result<Value, MethodError>> + Send + Sync>;
+
+pub struct ServerBuilder {
+    methods: HashMap<String, BoxedHandler>,
+    debug_errors: bool,
+}
 
 impl ServerBuilder {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            methods: HashMap::new(),
+            debug_errors: cfg!(debug_assertions),
+        }
     }
 
-    pub fn method(self, method: String, handler: impl MethodHandler) -> Self {
+    /// Whether `InternalError` responses include what went wrong, such as why a result couldn't
+    /// be serialized. That's handy while developing, but can leak implementation details to
+    /// clients, so it defaults to on in debug builds and off in release builds.
+    pub fn with_debug_errors(mut self, debug_errors: bool) -> Self {
+        self.debug_errors = debug_errors;
+        self
+    }
+
+    /// Registers `handler` to handle calls to `method`.
+    ///
+    /// # Panics
+    ///
+    /// If a handler is already registered for `method`.
+    pub fn method<H, T>(mut self, method: impl Into<String>, handler: H) -> Self
+    where
+        H: MethodHandler<T>,
+    {
+        let method = method.into();
+        assert!(
+            !self.methods.contains_key(&method),
+            "a handler is already registered for method {:?}",
+            method
+        );
+        let handler: BoxedHandler = Arc::new(move |params| Box::pin(handler.call(params)));
+        self.methods.insert(method, handler);
         self
     }
 
     pub fn build(self) -> Server {
-        Server {}
+        Server {
+            methods: Arc::new(self.methods),
+            debug_errors: self.debug_errors,
+        }
     }
 }
 
@@ -22,4 +68,350 @@ impl Default for ServerBuilder {
     }
 }
 
-pub struct Server {}
+/// Dispatches incoming messages to the handlers registered with a [`ServerBuilder`]. Cloning a
+/// server is cheap, and clones share their handlers.
+#[derive(Clone)]
+pub struct Server {
+    methods: Arc<HashMap<String, BoxedHandler>>,
+    debug_errors: bool,
+}
+
+impl Server {
+    /// Handles one message from a client, returning the message to send back, if any.
+    ///
+    /// Notifications never get a reply, and are dropped if there's no handler for them. Responses
+    /// are dropped too, since a server doesn't send requests of its own.
+    pub async fn handle_message(&self, message: Message) -> Option<Message> {
+        match message {
+            Message::Request(request) => {
+                Some(Message::Response(self.handle_request(request).await))
+            }
+            Message::Notification(notification) => {
+                self.handle_notification(notification).await;
+                None
+            }
+            Message::BatchRequest(batch) => {
+                let mut responses = Vec::new();
+                for entry in batch.into_entries() {
+                    match entry {
+                        BatchEntry::Request(request) => {
+                            responses.push(self.handle_request(request).await)
+                        }
+                        BatchEntry::Notification(notification) => {
+                            self.handle_notification(notification).await
+                        }
+                        BatchEntry::Invalid { response, .. } => responses.extend(response),
+                    }
+                }
+                // A batch of nothing but notifications gets no reply at all, not an empty array.
+                (!responses.is_empty()).then_some(Message::BatchResponse(responses))
+            }
+            Message::Response(_) | Message::BatchResponse(_) => None,
+        }
+    }
+
+    async fn handle_request(&self, request: Request) -> Response {
+        let (method, params, id) = request.into_parts();
+        let result = match self.methods.get(&method) {
+            Some(handler) => handler(params).await,
+            None => Err(MethodError::Response(ResponseError::new(
+                ErrorCode::MethodNotFound,
+                "Method not found",
+            ))),
+        };
+        match result {
+            Ok(result) => Response::ok(id, result),
+            Err(error) => Response::err(id, self.response_error(&method, error)),
+        }
+    }
+
+    async fn handle_notification(&self, notification: Notification) {
+        let (method, params) = notification.into_parts();
+        if let Some(handler) = self.methods.get(&method) {
+            // There's nobody to tell about the result or an error, but a result that can't be
+            // serialized is a bug worth logging either way.
+            if let Err(MethodError::Serialize(err)) = handler(params).await {
+                log_serialize_error(&method, &err);
+            }
+        }
+    }
+
+    fn response_error(&self, method: &str, error: MethodError) -> ResponseError {
+        match error {
+            MethodError::Response(error) => error,
+            MethodError::Serialize(err) => {
+                log_serialize_error(method, &err);
+                let error = ResponseError::new(ErrorCode::InternalError, "Internal error");
+                if self.debug_errors {
+                    error.with_data(Value::String(format!(
+                        "failed to serialize result: {}",
+                        err
+                    )))
+                } else {
+                    error
+                }
+            }
+        }
+    }
+}
+
+fn log_serialize_error(method: &str, err: &serde_json::Error) {
+    tracing::error!(method, error = %err, "failed to serialize method result");
+}
+
+impl fmt::Debug for Server {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Server")
+            .field("methods", &self.methods.keys().collect::<Vec<_>>())
+            .field("debug_errors", &self.debug_errors)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        extract::Params,
+        request::RequestId,
+        response::ResponseResult,
+        transport::{BatchBuilder, Decoder},
+    };
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    async fn subtract(Params((a, b)): Params<(i64, i64)>) -> Result<i64, ResponseError> {
+        Ok(a - b)
+    }
+
+    async fn echo(params: Option<Value>) -> Result<Option<Value>, ResponseError> {
+        Ok(params)
+    }
+
+    async fn version() -> Result<&'static str, ResponseError> {
+        Ok("1.0")
+    }
+
+    async fn withdraw() -> Result<i64, ResponseError> {
+        Err(ResponseError::new(
+            ErrorCode::ApplicationError(-32050),
+            "Insufficient funds",
+        ))
+    }
+
+    fn server() -> Server {
+        ServerBuilder::new()
+            .method("subtract", subtract)
+            .method("withdraw", withdraw)
+            .method("echo", echo)
+            .method("version", version)
+            .build()
+    }
+
+    fn request(method: &str, params: Option<Value>, id: i64) -> Message {
+        Message::Request(Request::new(method, params, RequestId::Number(id.into())))
+    }
+
+    fn expect_response(message: Option<Message>) -> Response {
+        match message {
+            Some(Message::Response(response)) => response,
+            other => panic!("expected a Response, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn dispatch_request() {
+        let response = expect_response(
+            server()
+                .handle_message(request("subtract", Some(json!([42, 23])), 1))
+                .await,
+        );
+        assert_eq!(
+            Response::ok(RequestId::Number(1.into()), json!(19)),
+            response
+        );
+
+        let response = expect_response(server().handle_message(request("version", None, 2)).await);
+        assert_eq!(
+            Response::ok(RequestId::Number(2.into()), json!("1.0")),
+            response
+        );
+    }
+
+    #[tokio::test]
+    async fn pass_raw_params() {
+        let response = expect_response(
+            server()
+                .handle_message(request("echo", Some(json!({"a": [1]})), 1))
+                .await,
+        );
+        assert_eq!(
+            Response::ok(RequestId::Number(1.into()), json!({"a": [1]})),
+            response
+        );
+    }
+
+    #[tokio::test]
+    async fn return_handler_error() {
+        let response = expect_response(
+            server()
+                .handle_message(Message::Request(Request::new(
+                    "withdraw",
+                    None,
+                    RequestId::String("abc".to_string()),
+                )))
+                .await,
+        );
+        assert_eq!(&RequestId::String("abc".to_string()), response.id());
+        match response.result() {
+            ResponseResult::Err(error) => {
+                assert_eq!(ErrorCode::ApplicationError(-32050), error.code());
+                assert_eq!("Insufficient funds", error.message());
+            }
+            ResponseResult::Ok(_) => panic!("expected Err result"),
+        }
+    }
+
+    #[tokio::test]
+    async fn reject_invalid_params() {
+        let response = expect_response(
+            server()
+                .handle_message(request("subtract", Some(json!({})), 1))
+                .await,
+        );
+        match response.result() {
+            ResponseResult::Err(error) => assert_eq!(ErrorCode::InvalidParams, error.code()),
+            ResponseResult::Ok(_) => panic!("expected Err result"),
+        }
+    }
+
+    #[tokio::test]
+    async fn method_not_found() {
+        let response = expect_response(server().handle_message(request("foobar", None, 1)).await);
+        assert_eq!(&RequestId::Number(1.into()), response.id());
+        match response.result() {
+            ResponseResult::Err(error) => assert_eq!(ErrorCode::MethodNotFound, error.code()),
+            ResponseResult::Ok(_) => panic!("expected Err result"),
+        }
+    }
+
+    #[tokio::test]
+    async fn run_notification_handler_without_reply() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let server = ServerBuilder::new()
+            .method("update", {
+                let calls = calls.clone();
+                move || {
+                    calls.fetch_add(1, Ordering::Relaxed);
+                    async { Ok::<_, ResponseError>(()) }
+                }
+            })
+            .build();
+
+        let reply = server
+            .handle_message(Message::Notification(Notification::new("update", None)))
+            .await;
+        assert!(reply.is_none());
+        assert_eq!(1, calls.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn swallow_notification_without_handler() {
+        let reply = server()
+            .handle_message(Message::Notification(Notification::new("foobar", None)))
+            .await;
+        assert!(reply.is_none());
+    }
+
+    #[tokio::test]
+    async fn dispatch_batch() {
+        let message = Decoder::new()
+            .decode(
+                br#"[
+                    {"jsonrpc": "2.0", "method": "subtract", "params": [42, 23], "id": 1},
+                    {"jsonrpc": "2.0", "method": "notify_hello", "params": [7]},
+                    {"foo": "boo"},
+                    {"jsonrpc": "2.0", "method": "foo.get", "id": 5}
+                ]"#,
+            )
+            .unwrap();
+
+        let responses = match server().handle_message(message).await {
+            Some(Message::BatchResponse(responses)) => responses,
+            other => panic!("expected a BatchResponse, got {:?}", other),
+        };
+        let codes: Vec<_> = responses
+            .iter()
+            .map(|response| match response.result() {
+                ResponseResult::Ok(_) => None,
+                ResponseResult::Err(error) => Some(error.code()),
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                None,
+                Some(ErrorCode::InvalidRequest),
+                Some(ErrorCode::MethodNotFound)
+            ],
+            codes
+        );
+    }
+
+    #[tokio::test]
+    async fn no_reply_to_batch_of_notifications() {
+        let mut builder = BatchBuilder::new();
+        builder.notification("notify_sum", None).unwrap();
+        builder.notification("notify_hello", None).unwrap();
+
+        let reply = server()
+            .handle_message(Message::BatchRequest(builder.build().unwrap()))
+            .await;
+        assert!(reply.is_none());
+    }
+
+    async fn unserializable() -> Result<HashMap<(i32, i32), i32>, ResponseError> {
+        Ok(HashMap::from([((1, 2), 3)]))
+    }
+
+    async fn call_unserializable(debug_errors: bool) -> ResponseError {
+        let server = ServerBuilder::new()
+            .method("unserializable", unserializable)
+            .with_debug_errors(debug_errors)
+            .build();
+        let response = expect_response(
+            server
+                .handle_message(request("unserializable", None, 1))
+                .await,
+        );
+        match response.result() {
+            ResponseResult::Err(error) => error.clone(),
+            ResponseResult::Ok(result) => panic!("expected Err result, got {}", result),
+        }
+    }
+
+    #[tokio::test]
+    async fn respond_to_unserializable_result_with_diagnostic() {
+        let error = call_unserializable(true).await;
+        assert_eq!(ErrorCode::InternalError, error.code());
+        assert_eq!(
+            Some(&json!("failed to serialize result: key must be a string")),
+            error.data()
+        );
+    }
+
+    #[tokio::test]
+    async fn respond_to_unserializable_result_without_diagnostic() {
+        let error = call_unserializable(false).await;
+        assert_eq!(ErrorCode::InternalError, error.code());
+        assert_eq!("Internal error", error.message());
+        assert_eq!(None, error.data());
+    }
+
+    #[test]
+    #[should_panic(expected = "already registered")]
+    fn reject_duplicate_method() {
+        ServerBuilder::new()
+            .method("version", version)
+            .method("version", version);
+    }
+}
@@ -0,0 +1,419 @@
+//! Test doubles for code that talks to a JSON-RPC 2.0 server.
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    io::{self, Read, Write},
+    sync::Mutex,
+};
+
+use serde_json::Value;
+
+use crate::{
+    notification::Notification,
+    request::Request,
+    response::{ErrorCode, Response, ResponseError, ResponseResult},
+    transport::{
+        codec::{CodecError, MessageCodec},
+        BatchEntry, Message,
+    },
+};
+
+/// A stand-in for a real server that answers requests from a list of declared expectations.
+///
+/// Any expectation that hasn't been satisfied by the time the mock is dropped causes a panic,
+/// as does any request or notification that didn't match an expectation.
+#[derive(Debug, Default)]
+pub struct MockServer {
+    expectations: Mutex<Vec<Expectation>>,
+    unexpected: Mutex<Vec<String>>,
+}
+
+impl MockServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares that `method` will be called. Without any further configuration, the expectation
+    /// matches any params, must be called at least once, and responds with a `null` result.
+    pub fn expect(&mut self, method: impl Into<String>) -> &mut Expectation {
+        let expectations = self
+            .expectations
+            .get_mut()
+            .expect("mock server lock poisoned");
+        expectations.push(Expectation::new(method.into()));
+        expectations.last_mut().unwrap()
+    }
+
+    /// Answers a request with the response of the first matching expectation that hasn't been
+    /// used up. Requests that don't match anything are answered with a `MethodNotFound` error.
+    pub fn handle_request(&self, request: &Request) -> Response {
+        match self.record_call(request.method(), request.params()) {
+            Some(result) => Response::new(request.id().clone(), result),
+            None => Response::err(
+                request.id().clone(),
+                ResponseError::new(ErrorCode::MethodNotFound, "Method not found"),
+            ),
+        }
+    }
+
+    pub fn handle_notification(&self, notification: &Notification) {
+        self.record_call(notification.method(), notification.params());
+    }
+
+    /// Connects the mock to client code that reads and writes bytes, such as
+    /// [`blocking::Client`](crate::blocking::Client), with messages framed by `codec`.
+    ///
+    /// Both halves of the connection are implemented on `&MockTransport`, so the same transport
+    /// can be passed as the reader and the writer.
+    pub fn transport<C: MessageCodec>(&self, codec: C) -> MockTransport<'_, C> {
+        MockTransport {
+            server: self,
+            codec: RefCell::new(codec),
+            incoming: RefCell::new(Vec::new()),
+            outgoing: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    fn handle_message(&self, message: Message) -> Option<Message> {
+        match message {
+            Message::Request(request) => Some(Message::Response(self.handle_request(&request))),
+            Message::Notification(notification) => {
+                self.handle_notification(&notification);
+                None
+            }
+            Message::BatchRequest(batch) => {
+                let responses: Vec<_> = batch
+                    .into_entries()
+                    .into_iter()
+                    .filter_map(|entry| match entry {
+                        BatchEntry::Request(request) => Some(self.handle_request(&request)),
+                        BatchEntry::Notification(notification) => {
+                            self.handle_notification(&notification);
+                            None
+                        }
+                        BatchEntry::Invalid { response, .. } => response,
+                    })
+                    .collect();
+                (!responses.is_empty()).then_some(Message::BatchResponse(responses))
+            }
+            // A server never answers responses.
+            Message::Response(_) | Message::BatchResponse(_) => None,
+        }
+    }
+
+    /// Panics if any expectation wasn't met or any unexpected call was received.
+    pub fn verify(&self) {
+        let mut failures = self
+            .unexpected
+            .lock()
+            .expect("mock server lock poisoned")
+            .clone();
+
+        for expectation in self
+            .expectations
+            .lock()
+            .expect("mock server lock poisoned")
+            .iter()
+        {
+            if !expectation.is_satisfied() {
+                failures.push(format!(
+                    "expected \"{}\" to be called {}, but it was called {} time(s)",
+                    expectation.method,
+                    expectation
+                        .times
+                        .map_or_else(|| "at least once".to_string(), |n| format!("{n} time(s)")),
+                    expectation.calls
+                ));
+            }
+        }
+
+        if !failures.is_empty() {
+            panic!("mock server verification failed:\n{}", failures.join("\n"));
+        }
+    }
+
+    fn record_call(&self, method: &str, params: Option<&Value>) -> Option<ResponseResult> {
+        let mut expectations = self.expectations.lock().expect("mock server lock poisoned");
+        match expectations
+            .iter_mut()
+            .find(|expectation| expectation.matches(method, params))
+        {
+            Some(expectation) => {
+                expectation.calls += 1;
+                Some(expectation.result.clone())
+            }
+            None => {
+                self.unexpected
+                    .lock()
+                    .expect("mock server lock poisoned")
+                    .push(format!(
+                        "unexpected call to \"{}\" with params {}",
+                        method,
+                        params.map_or_else(|| "<none>".to_string(), Value::to_string)
+                    ));
+                None
+            }
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        // Panicking while already unwinding would abort the test run and hide the original failure.
+        if !std::thread::panicking() {
+            self.verify();
+        }
+    }
+}
+
+/// An in-memory connection to a [`MockServer`], returned by [`MockServer::transport`].
+///
+/// Every message written to it is handled as soon as its frame is complete, and the responses
+/// are there to be read straight away. Reading when there's nothing left to read returns end of
+/// file, since the mock never has anything to say on its own.
+#[derive(Debug)]
+pub struct MockTransport<'a, C> {
+    server: &'a MockServer,
+    codec: RefCell<C>,
+    incoming: RefCell<Vec<u8>>,
+    outgoing: RefCell<VecDeque<u8>>,
+}
+
+impl<C: MessageCodec> Write for &MockTransport<'_, C> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut incoming = self.incoming.borrow_mut();
+        let mut codec = self.codec.borrow_mut();
+        incoming.extend_from_slice(buf);
+
+        loop {
+            let reply = match codec.decode(&mut incoming) {
+                Ok(Some(message)) => self.server.handle_message(message),
+                Ok(None) => break,
+                // Answered the way a real server would, with a Parse error or Invalid Request.
+                Err(CodecError::Decode(err)) => err.to_response().map(Message::Response),
+                Err(err) => return Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+            };
+            if let Some(reply) = reply {
+                let mut encoded = Vec::new();
+                codec
+                    .encode(&reply, &mut encoded)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                self.outgoing.borrow_mut().extend(encoded);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<C> Read for &MockTransport<'_, C> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.outgoing.borrow_mut().read(buf)
+    }
+}
+
+/// A single expected call declared with [`MockServer::expect`].
+#[derive(Debug)]
+pub struct Expectation {
+    method: String,
+    params: Option<Option<Value>>,
+    result: ResponseResult,
+    times: Option<usize>,
+    calls: usize,
+}
+
+impl Expectation {
+    fn new(method: String) -> Self {
+        Self {
+            method,
+            params: None,
+            result: ResponseResult::Ok(Value::Null),
+            times: None,
+            calls: 0,
+        }
+    }
+
+    /// Only match calls with exactly these params.
+    pub fn with_params(&mut self, params: Value) -> &mut Self {
+        self.params = Some(Some(params));
+        self
+    }
+
+    /// Only match calls that don't include params.
+    pub fn without_params(&mut self) -> &mut Self {
+        self.params = Some(None);
+        self
+    }
+
+    pub fn return_ok(&mut self, result: Value) -> &mut Self {
+        self.result = ResponseResult::Ok(result);
+        self
+    }
+
+    pub fn return_err(&mut self, error: ResponseError) -> &mut Self {
+        self.result = ResponseResult::Err(error);
+        self
+    }
+
+    /// Require exactly `n` calls. Once used up, the expectation stops matching.
+    pub fn times(&mut self, n: usize) -> &mut Self {
+        self.times = Some(n);
+        self
+    }
+
+    fn matches(&self, method: &str, params: Option<&Value>) -> bool {
+        self.method == method
+            && self
+                .params
+                .as_ref()
+                .is_none_or(|expected| expected.as_ref() == params)
+            && self.times.is_none_or(|times| self.calls < times)
+    }
+
+    fn is_satisfied(&self) -> bool {
+        match self.times {
+            Some(times) => self.calls == times,
+            None => self.calls > 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::RequestId;
+    use serde_json::json;
+
+    #[test]
+    fn returns_expected_result() {
+        let mut server = MockServer::new();
+        server
+            .expect("getUser")
+            .with_params(json!({"id": 1}))
+            .return_ok(json!({"name": "Ferris"}))
+            .times(1);
+
+        let response = server.handle_request(&Request::new(
+            "getUser",
+            Some(json!({"id": 1})),
+            RequestId::Number(1.into()),
+        ));
+        assert_eq!(
+            Response::ok(RequestId::Number(1.into()), json!({"name": "Ferris"})),
+            response
+        );
+    }
+
+    #[test]
+    fn returns_expected_error() {
+        let mut server = MockServer::new();
+        server.expect("getUser").return_err(ResponseError::new(
+            ErrorCode::InvalidParams,
+            "Invalid params",
+        ));
+
+        let response =
+            server.handle_request(&Request::new("getUser", None, RequestId::Number(1.into())));
+        match response.result() {
+            ResponseResult::Err(error) => assert_eq!(error.code(), ErrorCode::InvalidParams),
+            ResponseResult::Ok(_) => panic!("expected Err result"),
+        }
+    }
+
+    #[test]
+    fn matches_notifications() {
+        let mut server = MockServer::new();
+        server.expect("update").without_params();
+
+        server.handle_notification(&Notification::new("update", None));
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected call to \"getUser\"")]
+    fn panics_on_mismatched_params() {
+        let mut server = MockServer::new();
+        server.expect("getUser").with_params(json!({"id": 1}));
+
+        let response = server.handle_request(&Request::new(
+            "getUser",
+            Some(json!({"id": 2})),
+            RequestId::Number(1.into()),
+        ));
+        match response.result() {
+            ResponseResult::Err(error) => assert_eq!(error.code(), ErrorCode::MethodNotFound),
+            ResponseResult::Ok(_) => panic!("expected Err result"),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "expected \"getUser\" to be called 2 time(s)")]
+    fn panics_on_unmet_expectation() {
+        let mut server = MockServer::new();
+        server.expect("getUser").times(2);
+
+        server.handle_request(&Request::new("getUser", None, RequestId::Number(1.into())));
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected call to \"getUser\"")]
+    fn panics_on_too_many_calls() {
+        let mut server = MockServer::new();
+        server.expect("getUser").times(1);
+
+        server.handle_request(&Request::new("getUser", None, RequestId::Number(1.into())));
+        server.handle_request(&Request::new("getUser", None, RequestId::Number(2.into())));
+    }
+
+    #[test]
+    fn answers_over_transport() {
+        use crate::transport::codec::NewlineCodec;
+
+        let mut server = MockServer::new();
+        server
+            .expect("subtract")
+            .with_params(json!([42, 23]))
+            .return_ok(json!(19));
+        server.expect("update");
+
+        let transport = server.transport(NewlineCodec::new());
+        let mut writer = &transport;
+        // Split mid-frame, to check nothing is handled until the frame is complete.
+        writer
+            .write_all(br#"{"jsonrpc":"2.0","method":"subtract","#)
+            .unwrap();
+        writer
+            .write_all(b"\"params\":[42,23],\"id\":1}\n{\"jsonrpc\":\"2.0\",\"method\":\"update\"}\n{oops}\n")
+            .unwrap();
+
+        let mut output = String::new();
+        (&transport).read_to_string(&mut output).unwrap();
+        let mut lines = output.lines();
+        assert_eq!(
+            Some(r#"{"jsonrpc":"2.0","id":1,"result":19}"#),
+            lines.next()
+        );
+        assert!(lines.next().unwrap().contains(r#""code":-32700"#));
+        assert_eq!(None, lines.next());
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn drives_blocking_client() {
+        use crate::{blocking::Client, transport::codec::ContentLengthCodec};
+
+        let mut server = MockServer::new();
+        server
+            .expect("getUser")
+            .with_params(json!({"id": 1}))
+            .return_ok(json!({"name": "Ferris"}));
+
+        let transport = server.transport(ContentLengthCodec::new());
+        let mut client = Client::new(&transport, &transport, ContentLengthCodec::new());
+        let user: Value = client.call("getUser", json!({"id": 1})).unwrap();
+        assert_eq!(json!({"name": "Ferris"}), user);
+    }
+}
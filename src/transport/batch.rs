@@ -0,0 +1,396 @@
+use std::{collections::HashSet, fmt};
+
+use serde::{de, Deserialize, Serialize};
+use serde_json::Value;
+
+use super::DecodeError;
+use crate::{
+    notification::Notification,
+    request::{Request, RequestId},
+    response::Response,
+};
+
+/// A batch of requests and notifications, sent together as a JSON array.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchRequest {
+    entries: Vec<BatchEntry>,
+}
+
+impl BatchRequest {
+    /// Decodes a batch received from a peer. The spec calls for a single error response to an
+    /// empty batch, while each invalid entry gets its own error response instead of sinking the
+    /// whole batch.
+    pub(super) fn from_values(values: &[Value]) -> Result<Self, DecodeError> {
+        if values.is_empty() {
+            return Err(DecodeError::empty_batch());
+        }
+        let entries = values.iter().map(BatchEntry::from_value).collect();
+        Ok(Self { entries })
+    }
+
+    pub fn entries(&self) -> &[BatchEntry] {
+        &self.entries
+    }
+
+    pub fn into_entries(self) -> Vec<BatchEntry> {
+        self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The requests in the batch, skipping notifications.
+    pub fn requests(&self) -> impl Iterator<Item = &Request> {
+        self.entries.iter().filter_map(|entry| match entry {
+            BatchEntry::Request(request) => Some(request),
+            BatchEntry::Notification(_) | BatchEntry::Invalid { .. } => None,
+        })
+    }
+}
+
+impl Serialize for BatchRequest {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.entries.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BatchRequest {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let values = Vec::<Value>::deserialize(deserializer)?;
+        Self::from_values(&values).map_err(de::Error::custom)
+    }
+}
+
+/// One of the messages in a [`BatchRequest`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchEntry {
+    Request(Request),
+    Notification(Notification),
+    /// An entry that wasn't a valid request or notification. The spec says the rest of the batch
+    /// should still be processed, with this `response` in place of the entry's result. It's
+    /// `None` if the entry looked like a response, which mustn't be answered.
+    Invalid {
+        value: Value,
+        response: Option<Response>,
+    },
+}
+
+impl BatchEntry {
+    /// Decodes an entry of a batch received from a peer, keeping it as
+    /// [`Invalid`](Self::Invalid) if it isn't valid.
+    pub(super) fn from_value(value: &Value) -> Self {
+        if let Ok(notification) = Notification::deserialize(value) {
+            return BatchEntry::Notification(notification);
+        }
+        if let Ok(request) = Request::deserialize(value) {
+            return BatchEntry::Request(request);
+        }
+        BatchEntry::Invalid {
+            value: value.clone(),
+            response: DecodeError::invalid_message(value).to_response(),
+        }
+    }
+
+    /// The method of the entry, or `None` for invalid entries.
+    pub fn method(&self) -> Option<&str> {
+        match self {
+            BatchEntry::Request(request) => Some(request.method()),
+            BatchEntry::Notification(notification) => Some(notification.method()),
+            BatchEntry::Invalid { .. } => None,
+        }
+    }
+
+    /// The id of the entry, or `None` for notifications and invalid entries.
+    pub fn id(&self) -> Option<&RequestId> {
+        match self {
+            BatchEntry::Request(request) => Some(request.id()),
+            BatchEntry::Notification(_) | BatchEntry::Invalid { .. } => None,
+        }
+    }
+}
+
+impl Serialize for BatchEntry {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            BatchEntry::Request(request) => request.serialize(serializer),
+            BatchEntry::Notification(notification) => notification.serialize(serializer),
+            BatchEntry::Invalid { value, .. } => value.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BatchEntry {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        // Decoded the same way as entries of a whole batch, keeping invalid ones.
+        let value = Value::deserialize(deserializer)?;
+        Ok(BatchEntry::from_value(&value))
+    }
+}
+
+/// Builds a [`BatchRequest`], assigning ids that are unique within the batch and enforcing a
+/// maximum size.
+///
+/// Servers are free to cap how many entries they accept in a batch, so it's better to find out
+/// while building one than from an error response.
+#[derive(Debug, Default)]
+pub struct BatchBuilder {
+    entries: Vec<BatchEntry>,
+    ids: HashSet<RequestId>,
+    next_id: u64,
+    max_size: Option<usize>,
+}
+
+impl BatchBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Adds a request with the next free numeric id, which is returned to match up the response.
+    pub fn request(
+        &mut self,
+        method: impl Into<String>,
+        params: Option<Value>,
+    ) -> Result<RequestId, BatchError> {
+        self.check_size()?;
+        let id = loop {
+            self.next_id += 1;
+            let id = RequestId::Number(self.next_id.into());
+            if !self.ids.contains(&id) {
+                break id;
+            }
+        };
+        self.push_request(Request::new(method, params, id.clone()));
+        Ok(id)
+    }
+
+    /// Adds a request with an id of the caller's choosing, which must not already be in the batch.
+    pub fn request_with_id(
+        &mut self,
+        method: impl Into<String>,
+        params: Option<Value>,
+        id: RequestId,
+    ) -> Result<(), BatchError> {
+        self.check_size()?;
+        if self.ids.contains(&id) {
+            return Err(BatchError::DuplicateId(id));
+        }
+        self.push_request(Request::new(method, params, id));
+        Ok(())
+    }
+
+    pub fn notification(
+        &mut self,
+        method: impl Into<String>,
+        params: Option<Value>,
+    ) -> Result<(), BatchError> {
+        self.check_size()?;
+        self.entries
+            .push(BatchEntry::Notification(Notification::new(method, params)));
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Finishes the batch. The spec doesn't allow empty batches, so that's an error.
+    pub fn build(self) -> Result<BatchRequest, BatchError> {
+        if self.entries.is_empty() {
+            return Err(BatchError::Empty);
+        }
+        Ok(BatchRequest {
+            entries: self.entries,
+        })
+    }
+
+    fn check_size(&self) -> Result<(), BatchError> {
+        match self.max_size {
+            Some(max_size) if self.entries.len() >= max_size => Err(BatchError::TooLarge(max_size)),
+            _ => Ok(()),
+        }
+    }
+
+    fn push_request(&mut self, request: Request) {
+        self.ids.insert(request.id().clone());
+        self.entries.push(BatchEntry::Request(request));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchError {
+    /// A request with this id is already in the batch.
+    DuplicateId(RequestId),
+    /// The batch already has the maximum number of entries.
+    TooLarge(usize),
+    Empty,
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchError::DuplicateId(id) => write!(f, "duplicate request id {} in batch", id),
+            BatchError::TooLarge(max_size) => {
+                write!(f, "batch can't have more than {} entries", max_size)
+            }
+            BatchError::Empty => f.write_str("batch must have at least one entry"),
+        }
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn assign_unique_ids() {
+        let mut builder = BatchBuilder::new();
+        builder
+            .request_with_id("subtract", None, RequestId::Number(2.into()))
+            .unwrap();
+        assert_eq!(
+            RequestId::Number(1.into()),
+            builder.request("sum", None).unwrap()
+        );
+        assert_eq!(
+            RequestId::Number(3.into()),
+            builder.request("sum", None).unwrap()
+        );
+    }
+
+    #[test]
+    fn reject_duplicate_ids() {
+        let mut builder = BatchBuilder::new();
+        let id = builder.request("sum", None).unwrap();
+        assert_eq!(
+            Err(BatchError::DuplicateId(id.clone())),
+            builder.request_with_id("subtract", None, id)
+        );
+        assert_eq!(1, builder.len());
+    }
+
+    #[test]
+    fn enforce_max_size() {
+        let mut builder = BatchBuilder::new().with_max_size(2);
+        builder.request("sum", None).unwrap();
+        builder.notification("notify_hello", None).unwrap();
+        assert_eq!(
+            Err(BatchError::TooLarge(2)),
+            builder.notification("notify_hello", None)
+        );
+        assert!(builder.request("sum", None).is_err());
+    }
+
+    #[test]
+    fn reject_empty_batch() {
+        assert_eq!(Err(BatchError::Empty), BatchBuilder::new().build());
+    }
+
+    #[test]
+    fn serialize_mixed_batch() {
+        let mut builder = BatchBuilder::new();
+        builder.request("sum", Some(json!([1, 2, 4]))).unwrap();
+        builder
+            .notification("notify_hello", Some(json!([7])))
+            .unwrap();
+        let batch = builder.build().unwrap();
+
+        assert_eq!(
+            json!([
+                {"jsonrpc": "2.0", "method": "sum", "params": [1, 2, 4], "id": 1},
+                {"jsonrpc": "2.0", "method": "notify_hello", "params": [7]}
+            ]),
+            serde_json::to_value(&batch).unwrap()
+        );
+    }
+
+    #[test]
+    fn deserialize_mixed_batch() {
+        let batch: BatchRequest = serde_json::from_value(json!([
+            {"jsonrpc": "2.0", "method": "sum", "params": [1, 2, 4], "id": "1"},
+            {"jsonrpc": "2.0", "method": "notify_hello", "params": [7]}
+        ]))
+        .unwrap();
+
+        assert_eq!(2, batch.len());
+        assert_eq!(
+            Some(&RequestId::String("1".to_string())),
+            batch.entries()[0].id()
+        );
+        assert_eq!(Some("notify_hello"), batch.entries()[1].method());
+        assert_eq!(1, batch.requests().count());
+    }
+
+    #[test]
+    fn deserialize_invalid_entry() {
+        let entry: BatchEntry = serde_json::from_value(json!({"foo": "boo"})).unwrap();
+        match entry {
+            BatchEntry::Invalid { value, response } => {
+                assert_eq!(json!({"foo": "boo"}), value);
+                assert!(response.is_some());
+            }
+            other => panic!("expected Invalid entry, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn deserialize_empty_batch() {
+        assert!(serde_json::from_value::<BatchRequest>(json!([])).is_err());
+    }
+
+    #[test]
+    fn deserialize_batch_with_invalid_entries() {
+        let json = json!([
+            {"jsonrpc": "2.0", "method": "sum", "params": [1, 2, 4], "id": "1"},
+            {"foo": "boo"},
+            1
+        ]);
+        let batch: BatchRequest = serde_json::from_value(json.clone()).unwrap();
+
+        assert_eq!(3, batch.len());
+        assert_eq!(1, batch.requests().count());
+        match &batch.entries()[1] {
+            BatchEntry::Invalid {
+                value,
+                response: Some(response),
+            } => {
+                assert_eq!(&json!({"foo": "boo"}), value);
+                assert_eq!(&RequestId::Null, response.id());
+            }
+            other => panic!("expected Invalid entry, got {:?}", other),
+        }
+        // Decoding it as a Message goes through the same path.
+        match serde_json::from_value(json).unwrap() {
+            crate::transport::Message::BatchRequest(decoded) => assert_eq!(batch, decoded),
+            other => panic!("expected BatchRequest, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,605 @@
+//! Framing for transports that carry messages over a plain byte stream.
+
+use std::{borrow::Cow, fmt};
+
+use super::{DecodeError, Decoder, Message, SerializeOptions};
+
+/// Converts between [`Message`]s and the bytes of a particular wire framing.
+///
+/// Byte-stream transports own a buffer of bytes read from the peer and call
+/// [`decode`](MessageCodec::decode) until it returns `Ok(None)`, at which point they need to
+/// read more. Implement this to support a framing argonic doesn't ship with.
+pub trait MessageCodec {
+    /// Appends the framed encoding of `message` to `dst`.
+    fn encode(&mut self, message: &Message, dst: &mut Vec<u8>) -> Result<(), CodecError>;
+
+    /// Decodes the first complete frame in `src`, removing its bytes from the buffer. Returns
+    /// `Ok(None)` if there isn't a complete frame yet, leaving any partial frame in `src`.
+    fn decode(&mut self, src: &mut Vec<u8>) -> Result<Option<Message>, CodecError>;
+
+    /// Appends the framed encoding of a message that has already been serialized to compact
+    /// JSON, producing the same bytes as [`encode`](MessageCodec::encode) would. With the default
+    /// [`SerializeOptions`] the JSON is copied as it is; other options mean re-serializing it.
+    ///
+    /// The default implementation parses `json` back into a [`Message`] and calls
+    /// [`encode`](MessageCodec::encode), so codecs only need to override it to skip that.
+    fn encode_json(&mut self, json: &[u8], dst: &mut Vec<u8>) -> Result<(), CodecError> {
+        let message = serde_json::from_slice(json).map_err(CodecError::Encode)?;
+        self.encode(&message, dst)
+    }
+}
+
+/// One message per line, as used by JSON Lines and most stdio-based protocols. Lines may end
+/// with either `\n` or `\r\n`, and blank lines are ignored.
+#[derive(Debug, Default)]
+pub struct NewlineCodec {
+    decoder: Decoder,
+    serialize_options: SerializeOptions,
+}
+
+impl NewlineCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_decoder(mut self, decoder: Decoder) -> Self {
+        self.decoder = decoder;
+        self
+    }
+
+    /// Sets the options for outgoing messages. Output is always compact, even if the options ask
+    /// for pretty output.
+    pub fn with_serialize_options(mut self, serialize_options: SerializeOptions) -> Self {
+        self.serialize_options = serialize_options.single_line();
+        self
+    }
+}
+
+impl MessageCodec for NewlineCodec {
+    fn encode(&mut self, message: &Message, dst: &mut Vec<u8>) -> Result<(), CodecError> {
+        self.serialize_options
+            .to_writer(&mut *dst, message)
+            .map_err(CodecError::Encode)?;
+        dst.push(b'\n');
+        Ok(())
+    }
+
+    fn encode_json(&mut self, json: &[u8], dst: &mut Vec<u8>) -> Result<(), CodecError> {
+        let json = render_json(&self.serialize_options, json)?;
+        dst.extend_from_slice(&json);
+        dst.push(b'\n');
+        Ok(())
+    }
+
+    fn decode(&mut self, src: &mut Vec<u8>) -> Result<Option<Message>, CodecError> {
+        while let Some(end) = src.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = src.drain(..=end).collect();
+            let line = line.trim_ascii();
+            if line.is_empty() {
+                continue;
+            }
+            return self
+                .decoder
+                .decode(line)
+                .map(Some)
+                .map_err(CodecError::Decode);
+        }
+        Ok(None)
+    }
+}
+
+/// `Content-Length` headers followed by the message body, as used by the Language Server
+/// Protocol. Headers other than `Content-Length` are accepted and ignored.
+///
+/// Frames longer than the maximum frame length, 16 MiB unless set with
+/// [`with_max_frame_length`](Self::with_max_frame_length), are rejected as soon as their headers
+/// arrive, so a peer can't make us buffer an arbitrary amount of data.
+#[derive(Debug)]
+pub struct ContentLengthCodec {
+    decoder: Decoder,
+    serialize_options: SerializeOptions,
+    max_frame_length: usize,
+}
+
+const DEFAULT_MAX_FRAME_LENGTH: usize = 16 * 1024 * 1024;
+
+impl Default for ContentLengthCodec {
+    fn default() -> Self {
+        Self {
+            decoder: Decoder::default(),
+            serialize_options: SerializeOptions::default(),
+            max_frame_length: DEFAULT_MAX_FRAME_LENGTH,
+        }
+    }
+}
+
+impl ContentLengthCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The largest `Content-Length` to accept from the peer, in bytes.
+    pub fn with_max_frame_length(mut self, max_frame_length: usize) -> Self {
+        self.max_frame_length = max_frame_length;
+        self
+    }
+
+    pub fn with_decoder(mut self, decoder: Decoder) -> Self {
+        self.decoder = decoder;
+        self
+    }
+
+    pub fn with_serialize_options(mut self, serialize_options: SerializeOptions) -> Self {
+        self.serialize_options = serialize_options;
+        self
+    }
+}
+
+impl MessageCodec for ContentLengthCodec {
+    fn encode(&mut self, message: &Message, dst: &mut Vec<u8>) -> Result<(), CodecError> {
+        let body = self
+            .serialize_options
+            .to_vec(message)
+            .map_err(CodecError::Encode)?;
+        write_content_length_frame(&body, dst);
+        Ok(())
+    }
+
+    fn encode_json(&mut self, json: &[u8], dst: &mut Vec<u8>) -> Result<(), CodecError> {
+        let body = render_json(&self.serialize_options, json)?;
+        write_content_length_frame(&body, dst);
+        Ok(())
+    }
+
+    fn decode(&mut self, src: &mut Vec<u8>) -> Result<Option<Message>, CodecError> {
+        let Some(headers_end) = src.windows(4).position(|window| window == b"\r\n\r\n") else {
+            return Ok(None);
+        };
+
+        let headers = std::str::from_utf8(&src[..headers_end])
+            .map_err(|_| CodecError::Framing("headers are not valid UTF-8".to_string()))?;
+        let mut content_length = None;
+        for header in headers.split("\r\n") {
+            let (name, value) = header
+                .split_once(':')
+                .ok_or_else(|| CodecError::Framing(format!("malformed header: {:?}", header)))?;
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = Some(value.trim().parse::<usize>().map_err(|_| {
+                    CodecError::Framing(format!("invalid Content-Length: {:?}", value.trim()))
+                })?);
+            }
+        }
+        let content_length = content_length
+            .ok_or_else(|| CodecError::Framing("missing Content-Length header".to_string()))?;
+        if content_length > self.max_frame_length {
+            return Err(CodecError::Framing(format!(
+                "Content-Length {} exceeds the maximum of {}",
+                content_length, self.max_frame_length
+            )));
+        }
+
+        let body_start = headers_end + 4;
+        let frame_end = body_start
+            .checked_add(content_length)
+            .ok_or_else(|| CodecError::Framing("Content-Length is too large".to_string()))?;
+        if src.len() < frame_end {
+            return Ok(None);
+        }
+
+        let frame: Vec<u8> = src.drain(..frame_end).collect();
+        self.decoder
+            .decode(&frame[body_start..])
+            .map(Some)
+            .map_err(CodecError::Decode)
+    }
+}
+
+/// Renders JSON that was already serialized compactly with `options`. That's the JSON itself for
+/// the default options, and otherwise means decoding the message and serializing it again, so
+/// fields come out in the same order as from `encode`.
+fn render_json<'a>(
+    options: &SerializeOptions,
+    json: &'a [u8],
+) -> Result<Cow<'a, [u8]>, CodecError> {
+    if *options == SerializeOptions::default() {
+        return Ok(Cow::Borrowed(json));
+    }
+    let message: Message = serde_json::from_slice(json).map_err(CodecError::Encode)?;
+    options
+        .to_vec(&message)
+        .map(Cow::Owned)
+        .map_err(CodecError::Encode)
+}
+
+fn write_content_length_frame(body: &[u8], dst: &mut Vec<u8>) {
+    dst.extend_from_slice(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes());
+    dst.extend_from_slice(body);
+}
+
+/// JSON text sequences (RFC 7464, `application/json-seq`): each message is preceded by an ASCII
+/// record separator (0x1E) and followed by a line feed.
+///
+/// Anything before the first record separator is discarded, as are empty records, which lets
+/// the decoder resynchronize after a peer writes garbage or a truncated record.
+#[derive(Debug, Default)]
+pub struct JsonSeqCodec {
+    decoder: Decoder,
+    serialize_options: SerializeOptions,
+}
+
+const RECORD_SEPARATOR: u8 = 0x1E;
+
+impl JsonSeqCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_decoder(mut self, decoder: Decoder) -> Self {
+        self.decoder = decoder;
+        self
+    }
+
+    pub fn with_serialize_options(mut self, serialize_options: SerializeOptions) -> Self {
+        self.serialize_options = serialize_options;
+        self
+    }
+}
+
+impl MessageCodec for JsonSeqCodec {
+    fn encode(&mut self, message: &Message, dst: &mut Vec<u8>) -> Result<(), CodecError> {
+        dst.push(RECORD_SEPARATOR);
+        self.serialize_options
+            .to_writer(&mut *dst, message)
+            .map_err(CodecError::Encode)?;
+        dst.push(b'\n');
+        Ok(())
+    }
+
+    fn encode_json(&mut self, json: &[u8], dst: &mut Vec<u8>) -> Result<(), CodecError> {
+        let json = render_json(&self.serialize_options, json)?;
+        dst.push(RECORD_SEPARATOR);
+        dst.extend_from_slice(&json);
+        dst.push(b'\n');
+        Ok(())
+    }
+
+    fn decode(&mut self, src: &mut Vec<u8>) -> Result<Option<Message>, CodecError> {
+        loop {
+            let Some(start) = src.iter().position(|byte| *byte == RECORD_SEPARATOR) else {
+                src.clear();
+                return Ok(None);
+            };
+            src.drain(..start);
+
+            let next = src[1..].iter().position(|byte| *byte == RECORD_SEPARATOR);
+            let record = match next {
+                Some(end) => &src[1..=end],
+                None => &src[1..],
+            };
+
+            if record.trim_ascii().is_empty() {
+                match next {
+                    Some(end) => {
+                        src.drain(..=end);
+                        continue;
+                    }
+                    None => return Ok(None),
+                }
+            }
+
+            // Without a following separator we can't be sure the record is finished, but one
+            // that ends in a line feed and parses completely must be.
+            if next.is_none() && !record.ends_with(b"\n") {
+                return Ok(None);
+            }
+            let result = self.decoder.decode(record);
+            if next.is_none() && matches!(&result, Err(err) if err.is_eof()) {
+                return Ok(None);
+            }
+
+            let len = 1 + record.len();
+            src.drain(..len);
+            return result.map(Some).map_err(CodecError::Decode);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CodecError {
+    /// The bytes on the wire don't follow the codec's framing. The stream can't be trusted
+    /// after this, so transports should close the connection.
+    Framing(String),
+    /// A frame was read successfully but didn't contain a valid message. The frame has been
+    /// consumed, so decoding can continue with the next one.
+    Decode(DecodeError),
+    Encode(serde_json::Error),
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Framing(reason) => write!(f, "invalid framing: {}", reason),
+            CodecError::Decode(err) => write!(f, "failed to decode message: {}", err),
+            CodecError::Encode(err) => write!(f, "failed to encode message: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CodecError::Framing(_) => None,
+            CodecError::Decode(err) => Some(err),
+            CodecError::Encode(err) => Some(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{notification::Notification, request::Request, request::RequestId};
+    use serde_json::json;
+
+    fn request() -> Message {
+        Message::Request(Request::new(
+            "subtract",
+            Some(json!([42, 23])),
+            RequestId::Number(1.into()),
+        ))
+    }
+
+    fn notification() -> Message {
+        Message::Notification(Notification::new("update", None))
+    }
+
+    #[test]
+    fn newline_round_trip() {
+        let mut codec = NewlineCodec::new();
+        let mut buffer = Vec::new();
+        codec.encode(&request(), &mut buffer).unwrap();
+        codec.encode(&notification(), &mut buffer).unwrap();
+
+        assert_eq!(
+            b"{\"jsonrpc\":\"2.0\",\"method\":\"subtract\",\"params\":[42,23],\"id\":1}\n\
+              {\"jsonrpc\":\"2.0\",\"method\":\"update\"}\n"
+                .to_vec(),
+            buffer
+        );
+        assert!(matches!(
+            codec.decode(&mut buffer).unwrap(),
+            Some(Message::Request(_))
+        ));
+        assert!(matches!(
+            codec.decode(&mut buffer).unwrap(),
+            Some(Message::Notification(_))
+        ));
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn newline_serialize_options() {
+        let mut codec = NewlineCodec::new()
+            .with_serialize_options(SerializeOptions::pretty().with_sorted_keys(true));
+        let mut buffer = Vec::new();
+        codec.encode(&request(), &mut buffer).unwrap();
+        assert_eq!(
+            b"{\"id\":1,\"jsonrpc\":\"2.0\",\"method\":\"subtract\",\"params\":[42,23]}\n".to_vec(),
+            buffer
+        );
+    }
+
+    #[test]
+    fn newline_partial_frame() {
+        let mut codec = NewlineCodec::new();
+        let mut buffer = b"\r\n{\"jsonrpc\":\"2.0\",".to_vec();
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+
+        buffer.extend_from_slice(b"\"method\":\"update\"}\r\n");
+        assert!(matches!(
+            codec.decode(&mut buffer).unwrap(),
+            Some(Message::Notification(_))
+        ));
+    }
+
+    #[test]
+    fn newline_skips_invalid_frame() {
+        let mut codec = NewlineCodec::new();
+        let mut buffer = b"{oops}\n{\"jsonrpc\":\"2.0\",\"method\":\"update\"}\n".to_vec();
+        assert!(matches!(
+            codec.decode(&mut buffer),
+            Err(CodecError::Decode(_))
+        ));
+        assert!(matches!(
+            codec.decode(&mut buffer).unwrap(),
+            Some(Message::Notification(_))
+        ));
+    }
+
+    #[test]
+    fn content_length_round_trip() {
+        let mut codec = ContentLengthCodec::new();
+        let mut buffer = Vec::new();
+        codec.encode(&notification(), &mut buffer).unwrap();
+        codec.encode(&request(), &mut buffer).unwrap();
+
+        assert!(buffer.starts_with(b"Content-Length: 35\r\n\r\n{\"jsonrpc\""));
+        assert!(matches!(
+            codec.decode(&mut buffer).unwrap(),
+            Some(Message::Notification(_))
+        ));
+        assert!(matches!(
+            codec.decode(&mut buffer).unwrap(),
+            Some(Message::Request(_))
+        ));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn content_length_partial_frame() {
+        let mut codec = ContentLengthCodec::new();
+        let mut buffer = b"content-length: 35\r\n".to_vec();
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+
+        buffer
+            .extend_from_slice(b"Content-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n");
+        buffer.extend_from_slice(b"{\"jsonrpc\":\"2.0\",");
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+
+        buffer.extend_from_slice(b"\"method\":\"update\"}");
+        assert!(matches!(
+            codec.decode(&mut buffer).unwrap(),
+            Some(Message::Notification(_))
+        ));
+    }
+
+    #[test]
+    fn content_length_missing_header() {
+        let mut codec = ContentLengthCodec::new();
+        let mut buffer = b"Content-Type: application/json\r\n\r\n{}".to_vec();
+        assert!(matches!(
+            codec.decode(&mut buffer),
+            Err(CodecError::Framing(_))
+        ));
+    }
+
+    #[test]
+    fn content_length_invalid_header() {
+        let mut codec = ContentLengthCodec::new();
+        let mut buffer = b"Content-Length: lots\r\n\r\n{}".to_vec();
+        assert!(matches!(
+            codec.decode(&mut buffer),
+            Err(CodecError::Framing(_))
+        ));
+    }
+
+    #[test]
+    fn encode_json_matches_encode() {
+        fn check(mut codec: impl MessageCodec) {
+            let json = serde_json::to_vec(&request()).unwrap();
+            let (mut encoded, mut encoded_json) = (Vec::new(), Vec::new());
+            codec.encode(&request(), &mut encoded).unwrap();
+            codec.encode_json(&json, &mut encoded_json).unwrap();
+            assert_eq!(encoded, encoded_json);
+        }
+
+        check(NewlineCodec::new());
+        check(ContentLengthCodec::new());
+        check(JsonSeqCodec::new());
+
+        let sorted = SerializeOptions::compact().with_sorted_keys(true);
+        check(NewlineCodec::new().with_serialize_options(sorted));
+        check(ContentLengthCodec::new().with_serialize_options(SerializeOptions::pretty()));
+        check(JsonSeqCodec::new().with_serialize_options(sorted));
+    }
+
+    #[test]
+    fn content_length_overflowing_header() {
+        let mut codec = ContentLengthCodec::new().with_max_frame_length(usize::MAX);
+        let mut buffer = format!("Content-Length: {}\r\n\r\n{{}}", usize::MAX).into_bytes();
+        assert!(matches!(
+            codec.decode(&mut buffer),
+            Err(CodecError::Framing(_))
+        ));
+    }
+
+    #[test]
+    fn content_length_over_limit() {
+        let mut codec = ContentLengthCodec::new().with_max_frame_length(16);
+        // Rejected from the headers alone, without waiting for the body.
+        let mut buffer = b"Content-Length: 17\r\n\r\n".to_vec();
+        assert!(matches!(
+            codec.decode(&mut buffer),
+            Err(CodecError::Framing(_))
+        ));
+
+        let mut buffer = b"Content-Length: 16\r\n\r\n".to_vec();
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+    }
+
+    #[test]
+    fn json_seq_warns_once_for_split_record() {
+        use crate::transport::{NonFiniteNumbers, ProtocolWarningKind, ProtocolWarnings};
+        use std::sync::Arc;
+
+        let warnings = Arc::new(ProtocolWarnings::new());
+        let mut codec = JsonSeqCodec::new().with_decoder(
+            Decoder::new()
+                .with_non_finite_numbers(NonFiniteNumbers::Null)
+                .with_warnings(warnings.clone()),
+        );
+        let mut buffer =
+            b"\x1E{\"jsonrpc\":\"2.0\",\"method\":\"report\",\"params\":[NaN,\n".to_vec();
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+
+        buffer.extend_from_slice(b"1]}\n");
+        assert!(matches!(
+            codec.decode(&mut buffer).unwrap(),
+            Some(Message::Notification(_))
+        ));
+        assert_eq!(1, warnings.count(ProtocolWarningKind::NonFiniteNumbers));
+    }
+
+    #[test]
+    fn json_seq_round_trip() {
+        let mut codec = JsonSeqCodec::new();
+        let mut buffer = Vec::new();
+        codec.encode(&notification(), &mut buffer).unwrap();
+        codec.encode(&request(), &mut buffer).unwrap();
+
+        assert!(buffer.starts_with(b"\x1E{\"jsonrpc\":\"2.0\",\"method\":\"update\"}\n\x1E"));
+        assert!(matches!(
+            codec.decode(&mut buffer).unwrap(),
+            Some(Message::Notification(_))
+        ));
+        assert!(matches!(
+            codec.decode(&mut buffer).unwrap(),
+            Some(Message::Request(_))
+        ));
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn json_seq_pretty_record() {
+        let mut codec = JsonSeqCodec::new().with_serialize_options(SerializeOptions::pretty());
+        let mut encoded = Vec::new();
+        codec.encode(&request(), &mut encoded).unwrap();
+
+        // Feed the record in one byte at a time, since it contains line feeds of its own.
+        let mut buffer = Vec::new();
+        for (i, byte) in encoded.iter().enumerate() {
+            buffer.push(*byte);
+            let decoded = codec.decode(&mut buffer).unwrap();
+            assert_eq!(i == encoded.len() - 1, decoded.is_some());
+        }
+    }
+
+    #[test]
+    fn json_seq_skips_garbage_and_empty_records() {
+        let mut codec = JsonSeqCodec::new();
+        let mut buffer =
+            b"garbage\x1E\x1E\n\x1E{\"jsonrpc\":\"2.0\",\"method\":\"update\"}\n".to_vec();
+        assert!(matches!(
+            codec.decode(&mut buffer).unwrap(),
+            Some(Message::Notification(_))
+        ));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn json_seq_truncated_record() {
+        let mut codec = JsonSeqCodec::new();
+        let mut buffer =
+            b"\x1E{\"jsonrpc\":\"2.0\",\x1E{\"jsonrpc\":\"2.0\",\"method\":\"update\"}\n".to_vec();
+        assert!(matches!(
+            codec.decode(&mut buffer),
+            Err(CodecError::Decode(_))
+        ));
+        assert!(matches!(
+            codec.decode(&mut buffer).unwrap(),
+            Some(Message::Notification(_))
+        ));
+    }
+}
@@ -0,0 +1,896 @@
+use std::{
+    borrow::Cow,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use serde::{de, Deserialize};
+use serde_json::Value;
+
+use super::{BatchRequest, Message, ProtocolWarning, ProtocolWarnings};
+use crate::{
+    notification::Notification,
+    request::{Request, RequestId},
+    response::{ErrorCode, Response, ResponseError},
+};
+
+/// What to do with the `NaN`, `Infinity` and `-Infinity` literals some JSON encoders emit
+/// (Python's `json` module does by default). They aren't valid JSON, so by default the whole
+/// message is rejected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NonFiniteNumbers {
+    #[default]
+    Reject,
+    /// Replace them with `null`.
+    Null,
+    /// Replace them with the strings `"NaN"`, `"Infinity"` and `"-Infinity"`.
+    String,
+}
+
+/// What to do with strings that aren't valid Unicode: raw bytes that aren't UTF-8, or `\uXXXX`
+/// escapes of a surrogate without its other half. Peers written in languages with UTF-16 strings
+/// (JavaScript, Java, C#) send the latter more often than you'd think. By default the whole
+/// message is rejected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InvalidUnicode {
+    #[default]
+    Reject,
+    /// Replace invalid bytes and lone surrogates with U+FFFD REPLACEMENT CHARACTER.
+    Replace,
+    /// Keep lone surrogate escapes as the literal text of the escape, so `"\ud800"` becomes the
+    /// six characters `\ud800`. A Rust string can't hold bytes that aren't UTF-8, so those are
+    /// still replaced with U+FFFD.
+    PassThrough,
+}
+
+/// Turns incoming bytes into [`Message`]s, with knobs for tolerating misbehaving peers.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    non_finite_numbers: NonFiniteNumbers,
+    non_finite_replacements: AtomicU64,
+    invalid_unicode: InvalidUnicode,
+    warnings: Option<Arc<ProtocolWarnings>>,
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_non_finite_numbers(mut self, non_finite_numbers: NonFiniteNumbers) -> Self {
+        self.non_finite_numbers = non_finite_numbers;
+        self
+    }
+
+    pub fn with_invalid_unicode(mut self, invalid_unicode: InvalidUnicode) -> Self {
+        self.invalid_unicode = invalid_unicode;
+        self
+    }
+
+    /// Reports every deviation from the spec this decoder tolerates to `warnings`.
+    pub fn with_warnings(mut self, warnings: Arc<ProtocolWarnings>) -> Self {
+        self.warnings = Some(warnings);
+        self
+    }
+
+    pub fn decode(&self, input: &[u8]) -> Result<Message, DecodeError> {
+        // Replacements are only counted and reported once the message has decoded, so input that
+        // ends up rejected, or a partial frame a codec tries again later, doesn't count.
+        let mut replaced_unicode = false;
+        let input = match self.invalid_unicode {
+            InvalidUnicode::Reject => Cow::Borrowed(input),
+            mode => {
+                let input = replace_invalid_unicode(input, mode);
+                replaced_unicode = matches!(input, Cow::Owned(_));
+                input
+            }
+        };
+        let mut non_finite_replacements = 0;
+        let input = match self.non_finite_numbers {
+            NonFiniteNumbers::Reject => input,
+            mode => match replace_non_finite_numbers(&input, mode) {
+                (Cow::Owned(replaced_input), replaced) => {
+                    non_finite_replacements = replaced;
+                    Cow::Owned(replaced_input)
+                }
+                (Cow::Borrowed(_), _) => input,
+            },
+        };
+
+        let value: Value = serde_json::from_slice(&input).map_err(|source| DecodeError {
+            kind: DecodeErrorKind::Parse,
+            source,
+            id: None,
+            response_shaped: false,
+            response_id: None,
+        })?;
+        let message = Message::from_value(&value)?;
+
+        if replaced_unicode {
+            self.warn(ProtocolWarning::InvalidUnicode);
+        }
+        if non_finite_replacements > 0 {
+            self.non_finite_replacements
+                .fetch_add(non_finite_replacements, Ordering::Relaxed);
+            self.warn(ProtocolWarning::NonFiniteNumbers);
+        }
+        if let Some(warnings) = &self.warnings {
+            warnings.check(&message);
+        }
+        Ok(message)
+    }
+
+    /// The total number of non-finite literals this decoder has replaced, for keeping an eye on
+    /// how often peers actually send them.
+    pub fn non_finite_replacements(&self) -> u64 {
+        self.non_finite_replacements.load(Ordering::Relaxed)
+    }
+
+    fn warn(&self, warning: ProtocolWarning) {
+        if let Some(warnings) = &self.warnings {
+            warnings.report(warning);
+        }
+    }
+}
+
+/// How far a piece of input got towards being a valid message before decoding failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    /// The input wasn't valid JSON.
+    Parse,
+    /// The input was valid JSON, but nothing like a message: a scalar or an object without any
+    /// JSON-RPC fields.
+    InvalidShape,
+    /// The input looked like a message, but some of its fields were missing or had the wrong
+    /// type (a request whose method is a number, for example).
+    InvalidFields,
+}
+
+/// The reason some input couldn't be decoded into a [`Message`].
+#[derive(Debug)]
+pub struct DecodeError {
+    kind: DecodeErrorKind,
+    source: serde_json::Error,
+    id: Option<RequestId>,
+    /// The input looked like a response, which the spec says must never be answered.
+    response_shaped: bool,
+    response_id: Option<RequestId>,
+}
+
+impl DecodeError {
+    /// Picks the most useful error to report for a value that didn't match any kind of message.
+    pub(super) fn invalid_message(value: &Value) -> Self {
+        let (kind, source) = match value {
+            Value::Object(fields) if fields.contains_key("method") => {
+                let source = if fields.contains_key("id") {
+                    error_of::<Request>(value)
+                } else {
+                    error_of::<Notification>(value)
+                };
+                (DecodeErrorKind::InvalidFields, source)
+            }
+            Value::Object(fields)
+                if fields.contains_key("result") || fields.contains_key("error") =>
+            {
+                (DecodeErrorKind::InvalidFields, error_of::<Response>(value))
+            }
+            // Most likely a request that's missing its method.
+            Value::Object(fields) if fields.contains_key("jsonrpc") => {
+                (DecodeErrorKind::InvalidFields, error_of::<Request>(value))
+            }
+            Value::Array(_) => (
+                DecodeErrorKind::InvalidFields,
+                error_of::<BatchRequest>(value),
+            ),
+            _ => (
+                DecodeErrorKind::InvalidShape,
+                de::Error::custom("expected a JSON-RPC 2.0 message object or batch"),
+            ),
+        };
+        // The spec allows echoing the id in the error response as long as we could read it, which
+        // makes it much easier for the peer to figure out which of its requests was the problem.
+        // Only requests have an id worth echoing, though.
+        let is_request = value.get("method").is_some();
+        let id = value
+            .get("id")
+            .filter(|_| is_request)
+            .and_then(|id| RequestId::deserialize(id).ok());
+        let response_shaped = looks_like_response(value);
+        let response_id = value
+            .get("id")
+            .filter(|_| response_shaped)
+            .and_then(|id| RequestId::deserialize(id).ok());
+
+        Self {
+            kind,
+            source,
+            id,
+            response_shaped,
+            response_id,
+        }
+    }
+
+    /// For a batch of responses with an entry that isn't a valid response. The batch can't be
+    /// answered any more than a single response can.
+    pub(super) fn invalid_batch_response(value: &Value) -> Self {
+        Self {
+            kind: DecodeErrorKind::InvalidFields,
+            source: error_of::<Response>(value),
+            id: None,
+            response_shaped: true,
+            response_id: value
+                .get("id")
+                .and_then(|id| RequestId::deserialize(id).ok()),
+        }
+    }
+
+    pub(super) fn empty_batch() -> Self {
+        Self {
+            kind: DecodeErrorKind::InvalidShape,
+            source: de::Error::custom("batch must not be empty"),
+            id: None,
+            response_shaped: false,
+            response_id: None,
+        }
+    }
+
+    pub fn kind(&self) -> DecodeErrorKind {
+        self.kind
+    }
+
+    /// The error code the JSON-RPC 2.0 spec says to respond with.
+    pub fn error_code(&self) -> ErrorCode {
+        match self.kind {
+            DecodeErrorKind::Parse => ErrorCode::ParseError,
+            DecodeErrorKind::InvalidShape | DecodeErrorKind::InvalidFields => {
+                ErrorCode::InvalidRequest
+            }
+        }
+    }
+
+    /// The id of the offending request, if the input was a request with a valid id field.
+    pub fn id(&self) -> Option<&RequestId> {
+        self.id.as_ref()
+    }
+
+    /// The id of the response, if the input looked like a response with a valid id field, so a
+    /// client can tell whether it was the answer to one of its calls.
+    pub fn response_id(&self) -> Option<&RequestId> {
+        self.response_id.as_ref()
+    }
+
+    /// The error response the spec says to send back for this input, or `None` if the input
+    /// looked like a response, since replying to a response isn't allowed.
+    pub fn to_response(&self) -> Option<Response> {
+        if self.response_shaped {
+            return None;
+        }
+        let error = match self.error_code() {
+            ErrorCode::ParseError => ResponseError::new(ErrorCode::ParseError, "Parse error"),
+            code => ResponseError::new(code, "Invalid Request"),
+        };
+        Some(Response::err(
+            self.id.clone().unwrap_or(RequestId::Null),
+            error.with_data(Value::String(self.source.to_string())),
+        ))
+    }
+
+    /// Whether the input ended before a complete JSON value did, meaning more input might make
+    /// it valid.
+    pub fn is_eof(&self) -> bool {
+        self.kind == DecodeErrorKind::Parse && self.source.is_eof()
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            DecodeErrorKind::Parse => write!(f, "invalid JSON: {}", self.source),
+            DecodeErrorKind::InvalidShape | DecodeErrorKind::InvalidFields => {
+                write!(f, "invalid message: {}", self.source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Whether `value` has the members of a response and not those of a request.
+pub(super) fn looks_like_response(value: &Value) -> bool {
+    value.get("method").is_none() && (value.get("result").is_some() || value.get("error").is_some())
+}
+
+fn error_of<'de, T: Deserialize<'de>>(value: &'de Value) -> serde_json::Error {
+    match crate::de::from_value::<T>(value) {
+        Err(err) => err,
+        // Every variant has already failed by the time we get here, so this shouldn't happen.
+        Ok(_) => de::Error::custom("data did not match any variant of Message"),
+    }
+}
+
+/// Replaces every non-finite literal outside of a string, returning the rewritten input and the
+/// number of replacements. Only allocates if there's something to replace.
+fn replace_non_finite_numbers(input: &[u8], mode: NonFiniteNumbers) -> (Cow<'_, [u8]>, u64) {
+    const LITERALS: [&[u8]; 3] = [b"-Infinity", b"Infinity", b"NaN"];
+
+    let mut output: Option<Vec<u8>> = None;
+    let mut replaced = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut i = 0;
+
+    while i < input.len() {
+        let byte = input[i];
+
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+        } else if byte == b'"' {
+            in_string = true;
+        } else if let Some(literal) = LITERALS
+            .iter()
+            .find(|literal| input[i..].starts_with(literal))
+        {
+            // Outside of strings the only other bare words are true, false and null, so this
+            // can't misfire on valid JSON.
+            let output = output.get_or_insert_with(|| input[..i].to_vec());
+            match mode {
+                NonFiniteNumbers::Null => output.extend_from_slice(b"null"),
+                _ => {
+                    output.push(b'"');
+                    output.extend_from_slice(literal);
+                    output.push(b'"');
+                }
+            }
+            replaced += 1;
+            i += literal.len();
+            continue;
+        }
+
+        if let Some(output) = &mut output {
+            output.push(byte);
+        }
+        i += 1;
+    }
+
+    match output {
+        Some(output) => (Cow::Owned(output), replaced),
+        None => (Cow::Borrowed(input), 0),
+    }
+}
+
+/// Replaces bytes that aren't UTF-8 and lone surrogate escapes inside strings. Only allocates if
+/// there's something to replace.
+fn replace_invalid_unicode(input: &[u8], mode: InvalidUnicode) -> Cow<'_, [u8]> {
+    let input = match String::from_utf8_lossy(input) {
+        Cow::Borrowed(input) => Cow::Borrowed(input.as_bytes()),
+        Cow::Owned(input) => Cow::Owned(input.into_bytes()),
+    };
+
+    let mut output: Option<Vec<u8>> = None;
+    let mut in_string = false;
+    let mut i = 0;
+
+    while i < input.len() {
+        let byte = input[i];
+        let mut len = 1;
+
+        if in_string {
+            match byte {
+                b'\\' => match surrogate_escape(&input[i..]) {
+                    Some(Surrogate::Lone) => {
+                        let output = output.get_or_insert_with(|| input[..i].to_vec());
+                        match mode {
+                            InvalidUnicode::PassThrough => {
+                                // Escaping the backslash turns the escape into plain text.
+                                output.push(b'\\');
+                                output.extend_from_slice(&input[i..i + 6]);
+                            }
+                            _ => output.extend_from_slice(b"\\ufffd"),
+                        }
+                        i += 6;
+                        continue;
+                    }
+                    Some(Surrogate::Pair) => len = 12,
+                    // Skipping the escaped character means an escaped quote can't end the string.
+                    None => len = 2,
+                },
+                b'"' => in_string = false,
+                _ => {}
+            }
+        } else if byte == b'"' {
+            in_string = true;
+        }
+
+        let end = (i + len).min(input.len());
+        if let Some(output) = &mut output {
+            output.extend_from_slice(&input[i..end]);
+        }
+        i = end;
+    }
+
+    match output {
+        Some(output) => Cow::Owned(output),
+        None => input,
+    }
+}
+
+enum Surrogate {
+    Pair,
+    Lone,
+}
+
+/// Checks whether `input`, which starts with a backslash, starts with a `\uXXXX` escape of a
+/// surrogate.
+fn surrogate_escape(input: &[u8]) -> Option<Surrogate> {
+    let unit = |input: &[u8]| {
+        let hex = input.strip_prefix(b"\\u")?.get(..4)?;
+        u16::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()
+    };
+
+    match unit(input)? {
+        0xD800..=0xDBFF => match input.get(6..).and_then(unit) {
+            Some(0xDC00..=0xDFFF) => Some(Surrogate::Pair),
+            _ => Some(Surrogate::Lone),
+        },
+        0xDC00..=0xDFFF => Some(Surrogate::Lone),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        response::ResponseResult,
+        transport::{BatchEntry, ProtocolWarningKind},
+    };
+    use serde_json::json;
+
+    const MESSAGE: &[u8] =
+        br#"{"jsonrpc":"2.0","method":"report","params":[NaN, Infinity, -Infinity, "NaN \"Infinity\""]}"#;
+
+    fn params(message: Message) -> serde_json::Value {
+        match message {
+            Message::Notification(notification) => notification.params().unwrap().clone(),
+            other => panic!("expected Notification variant, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reject_non_finite_numbers_by_default() {
+        let decoder = Decoder::new();
+        assert!(decoder.decode(MESSAGE).is_err());
+        assert_eq!(0, decoder.non_finite_replacements());
+    }
+
+    #[test]
+    fn replace_non_finite_numbers_with_null() {
+        let decoder = Decoder::new().with_non_finite_numbers(NonFiniteNumbers::Null);
+        assert_eq!(
+            json!([null, null, null, "NaN \"Infinity\""]),
+            params(decoder.decode(MESSAGE).unwrap())
+        );
+        assert_eq!(3, decoder.non_finite_replacements());
+    }
+
+    #[test]
+    fn replace_non_finite_numbers_with_strings() {
+        let decoder = Decoder::new().with_non_finite_numbers(NonFiniteNumbers::String);
+        assert_eq!(
+            json!(["NaN", "Infinity", "-Infinity", "NaN \"Infinity\""]),
+            params(decoder.decode(MESSAGE).unwrap())
+        );
+        assert_eq!(3, decoder.non_finite_replacements());
+    }
+
+    #[test]
+    fn leave_valid_messages_untouched() {
+        let input = br#"{"jsonrpc":"2.0","method":"NaN","params":["\\", -1.5e3, true]}"#;
+        let (output, replaced) = replace_non_finite_numbers(input, NonFiniteNumbers::Null);
+        assert!(matches!(output, Cow::Borrowed(_)));
+        assert_eq!(0, replaced);
+    }
+
+    fn string_param(decoder: &Decoder, input: &[u8]) -> String {
+        match params(decoder.decode(input).unwrap()) {
+            Value::Array(mut params) => match params.remove(0) {
+                Value::String(param) => param,
+                other => panic!("expected a string, got {:?}", other),
+            },
+            other => panic!("expected an array, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reject_invalid_unicode_by_default() {
+        let error = decode_error(b"{\"jsonrpc\":\"2.0\",\"method\":\"log\",\"params\":[\"\xff\"]}");
+        assert_eq!(DecodeErrorKind::Parse, error.kind());
+
+        let error = decode_error(br#"{"jsonrpc":"2.0","method":"log","params":["\ud800"]}"#);
+        assert_eq!(DecodeErrorKind::Parse, error.kind());
+    }
+
+    #[test]
+    fn replace_invalid_unicode_with_replacement_character() {
+        let decoder = Decoder::new().with_invalid_unicode(InvalidUnicode::Replace);
+        assert_eq!(
+            "a\u{FFFD}b",
+            string_param(
+                &decoder,
+                b"{\"jsonrpc\":\"2.0\",\"method\":\"log\",\"params\":[\"a\xffb\"]}"
+            )
+        );
+        assert_eq!(
+            "\u{10000}\u{FFFD}x",
+            string_param(
+                &decoder,
+                br#"{"jsonrpc":"2.0","method":"log","params":["\ud800\udc00\udc00x"]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn pass_lone_surrogates_through() {
+        let decoder = Decoder::new().with_invalid_unicode(InvalidUnicode::PassThrough);
+        assert_eq!(
+            r"\udc00 and \ud83d",
+            string_param(
+                &decoder,
+                br#"{"jsonrpc":"2.0","method":"log","params":["\udc00 and \ud83d"]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn leave_valid_unicode_untouched() {
+        let input = r#"{"jsonrpc":"2.0","method":"\"x","params":["\ud83d\ude00", "\\ud800", "é"]}"#;
+        assert!(matches!(
+            replace_invalid_unicode(input.as_bytes(), InvalidUnicode::Replace),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn warn_about_tolerated_deviations() {
+        let reported = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let warnings = Arc::new(ProtocolWarnings::new().with_callback({
+            let reported = reported.clone();
+            move |warning| reported.lock().unwrap().push(warning.clone())
+        }));
+        let decoder = Decoder::new()
+            .with_non_finite_numbers(NonFiniteNumbers::Null)
+            .with_invalid_unicode(InvalidUnicode::Replace)
+            .with_warnings(warnings.clone());
+
+        decoder.decode(MESSAGE).unwrap();
+        decoder
+            .decode(br#"{"jsonrpc":"2.0","method":"log","params":["\ud800"]}"#)
+            .unwrap();
+        decoder
+            .decode(br#"{"jsonrpc":"2.0","method":"sum","id":1.5}"#)
+            .unwrap();
+        decoder
+            .decode(
+                br#"[{"jsonrpc":"2.0","method":"sum","id":1},{"jsonrpc":"2.0","method":"sum","id":1}]"#,
+            )
+            .unwrap();
+
+        assert_eq!(
+            vec![
+                ProtocolWarning::NonFiniteNumbers,
+                ProtocolWarning::InvalidUnicode,
+                ProtocolWarning::FractionalId(RequestId::Number(
+                    serde_json::Number::from_f64(1.5).unwrap()
+                )),
+                ProtocolWarning::DuplicateId(RequestId::Number(1.into())),
+            ],
+            *reported.lock().unwrap()
+        );
+        assert_eq!(1, warnings.count(ProtocolWarningKind::DuplicateId));
+        assert_eq!(4, warnings.total());
+    }
+
+    #[test]
+    fn no_warnings_for_valid_messages() {
+        let warnings = Arc::new(ProtocolWarnings::new());
+        let decoder = Decoder::new().with_warnings(warnings.clone());
+        decoder
+            .decode(br#"[{"jsonrpc":"2.0","method":"sum","id":1},{"jsonrpc":"2.0","method":"sum","id":2.0}]"#)
+            .unwrap();
+        assert_eq!(0, warnings.total());
+    }
+
+    #[test]
+    fn no_warnings_for_rejected_input() {
+        let warnings = Arc::new(ProtocolWarnings::new());
+        let decoder = Decoder::new()
+            .with_non_finite_numbers(NonFiniteNumbers::Null)
+            .with_invalid_unicode(InvalidUnicode::Replace)
+            .with_warnings(warnings.clone());
+        assert!(decoder
+            .decode(br#"{"jsonrpc":"2.0","params":[NaN, "\ud800"]}"#)
+            .is_err());
+        assert_eq!(0, warnings.total());
+        assert_eq!(0, decoder.non_finite_replacements());
+    }
+
+    fn decode_error(input: &[u8]) -> DecodeError {
+        Decoder::new().decode(input).unwrap_err()
+    }
+
+    #[test]
+    fn classify_invalid_json() {
+        let error = decode_error(br#"{"jsonrpc":"2.0","method":"#);
+        assert_eq!(DecodeErrorKind::Parse, error.kind());
+        assert_eq!(ErrorCode::ParseError, error.error_code());
+        assert!(error.is_eof());
+
+        let error = decode_error(br#"{"jsonrpc":"2.0",,}"#);
+        assert_eq!(DecodeErrorKind::Parse, error.kind());
+        assert!(!error.is_eof());
+    }
+
+    #[test]
+    fn classify_wrong_shape() {
+        for input in [&b"42"[..], b"\"subtract\"", b"null", br#"{"foo":"bar"}"#] {
+            let error = decode_error(input);
+            assert_eq!(DecodeErrorKind::InvalidShape, error.kind());
+            assert_eq!(ErrorCode::InvalidRequest, error.error_code());
+        }
+    }
+
+    #[test]
+    fn classify_invalid_fields() {
+        let error = decode_error(br#"{"jsonrpc":"2.0","method":1,"params":"bar","id":1}"#);
+        assert_eq!(DecodeErrorKind::InvalidFields, error.kind());
+        assert_eq!(ErrorCode::InvalidRequest, error.error_code());
+        assert!(error.to_string().contains("invalid type"), "{}", error);
+    }
+
+    #[test]
+    fn report_missing_method() {
+        let error = decode_error(br#"{"jsonrpc":"2.0","params":[1],"id":1}"#);
+        assert_eq!(DecodeErrorKind::InvalidFields, error.kind());
+        assert!(
+            error.to_string().contains("missing field `method`"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn report_invalid_response() {
+        let error = decode_error(
+            br#"{"jsonrpc":"2.0","result":1,"error":{"code":1,"message":"oops"},"id":1}"#,
+        );
+        assert_eq!(DecodeErrorKind::InvalidFields, error.kind());
+        assert!(
+            error.to_string().contains("both result and error"),
+            "{}",
+            error
+        );
+    }
+
+    #[cfg(feature = "error-paths")]
+    #[test]
+    fn report_path_of_invalid_field() {
+        let error = decode_error(br#"{"jsonrpc":"2.0","method":1,"id":1}"#);
+        assert!(
+            error.to_string().contains("method: invalid type"),
+            "{}",
+            error
+        );
+    }
+
+    #[cfg(feature = "error-paths")]
+    #[test]
+    fn report_path_of_invalid_batch_entry() {
+        let entries = batch(
+            br#"[{"jsonrpc":"2.0","method":"sum","id":1},{"jsonrpc":"2.0","method":1,"id":2}]"#,
+        );
+        match &entries[1] {
+            BatchEntry::Invalid {
+                response: Some(response),
+                ..
+            } => match response.result() {
+                ResponseResult::Err(error) => {
+                    let data = error.data().unwrap().as_str().unwrap();
+                    assert!(data.starts_with("method: invalid type"), "{}", data);
+                }
+                ResponseResult::Ok(_) => panic!("expected Err result"),
+            },
+            other => panic!("expected Invalid entry, got {:?}", other),
+        }
+    }
+
+    fn batch(input: &[u8]) -> Vec<BatchEntry> {
+        match Decoder::new().decode(input).unwrap() {
+            Message::BatchRequest(batch) => batch.into_entries(),
+            other => panic!("expected BatchRequest variant, got {:?}", other),
+        }
+    }
+
+    fn assert_invalid_request(entry: &BatchEntry) {
+        match entry {
+            BatchEntry::Invalid {
+                response: Some(response),
+                ..
+            } => {
+                assert_eq!(&RequestId::Null, response.id());
+                match response.result() {
+                    ResponseResult::Err(error) => {
+                        assert_eq!(ErrorCode::InvalidRequest, error.code())
+                    }
+                    ResponseResult::Ok(_) => panic!("expected Err result"),
+                }
+            }
+            other => panic!("expected Invalid entry, got {:?}", other),
+        }
+    }
+
+    // The batch examples from section 7 of the spec.
+
+    #[test]
+    fn reject_batch_with_invalid_json() {
+        let error = decode_error(
+            br#"[
+                {"jsonrpc": "2.0", "method": "sum", "params": [1,2,4], "id": "1"},
+                {"jsonrpc": "2.0", "method"
+            ]"#,
+        );
+        assert_eq!(ErrorCode::ParseError, error.error_code());
+    }
+
+    #[test]
+    fn reject_empty_batch() {
+        let error = decode_error(b"[]");
+        assert_eq!(DecodeErrorKind::InvalidShape, error.kind());
+        let response = error.to_response().unwrap();
+        assert_eq!(&RequestId::Null, response.id());
+        match response.result() {
+            ResponseResult::Err(error) => assert_eq!(ErrorCode::InvalidRequest, error.code()),
+            ResponseResult::Ok(_) => panic!("expected Err result"),
+        }
+    }
+
+    #[test]
+    fn reject_batch_response_with_malformed_entry() {
+        let error = decode_error(
+            br#"[
+                {"jsonrpc": "2.0", "result": 7, "id": "1"},
+                {"jsonrpc": "2.0", "result": 19, "error": {"code": 1, "message": "oops"}, "id": "2"},
+                {"jsonrpc": "2.0", "result": ["hello", 5], "id": "9"}
+            ]"#,
+        );
+        assert_eq!(DecodeErrorKind::InvalidFields, error.kind());
+        assert_eq!(
+            Some(&RequestId::String("2".to_string())),
+            error.response_id()
+        );
+        assert_eq!(None, error.to_response());
+    }
+
+    #[test]
+    fn keep_stray_response_in_request_batch_unanswered() {
+        let entries = batch(
+            br#"[
+                {"jsonrpc": "2.0", "method": "sum", "params": [1,2,4], "id": "1"},
+                {"jsonrpc": "2.0", "method": "subtract", "params": [42,23], "id": "2"},
+                {"jsonrpc": "2.0", "result": 7, "id": "3"}
+            ]"#,
+        );
+        assert!(matches!(
+            entries[2],
+            BatchEntry::Invalid { response: None, .. }
+        ));
+    }
+
+    #[test]
+    fn keep_invalid_entries_of_batch() {
+        let entries = batch(b"[1]");
+        assert_eq!(1, entries.len());
+        assert_invalid_request(&entries[0]);
+
+        let entries = batch(b"[1, 2, 3]");
+        assert_eq!(3, entries.len());
+        entries.iter().for_each(assert_invalid_request);
+    }
+
+    #[test]
+    fn decode_valid_entries_alongside_invalid_ones() {
+        let entries = batch(
+            br#"[
+                {"jsonrpc": "2.0", "method": "sum", "params": [1,2,4], "id": "1"},
+                {"jsonrpc": "2.0", "method": "notify_hello", "params": [7]},
+                {"jsonrpc": "2.0", "method": "subtract", "params": [42,23], "id": "2"},
+                {"foo": "boo"},
+                {"jsonrpc": "2.0", "method": "foo.get", "params": {"name": "myself"}, "id": "5"},
+                {"jsonrpc": "2.0", "method": "get_data", "id": "9"}
+            ]"#,
+        );
+        assert_eq!(6, entries.len());
+        assert!(matches!(entries[0], BatchEntry::Request(_)));
+        assert!(matches!(entries[1], BatchEntry::Notification(_)));
+        assert_invalid_request(&entries[3]);
+        assert!(matches!(entries[5], BatchEntry::Request(_)));
+    }
+
+    #[test]
+    fn echo_id_of_invalid_batch_entry() {
+        let entries = batch(br#"[{"jsonrpc": "2.0", "method": 1, "id": 3}]"#);
+        match &entries[0] {
+            BatchEntry::Invalid {
+                response: Some(response),
+                ..
+            } => {
+                assert_eq!(&RequestId::Number(3.into()), response.id())
+            }
+            other => panic!("expected Invalid entry, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn capture_id_of_invalid_request() {
+        let error = decode_error(br#"{"jsonrpc":"2.0","method":1,"id":"abc"}"#);
+        assert_eq!(Some(&RequestId::String("abc".to_string())), error.id());
+
+        let response = error.to_response().unwrap();
+        assert_eq!(&RequestId::String("abc".to_string()), response.id());
+        match response.result() {
+            ResponseResult::Err(error) => {
+                assert_eq!(ErrorCode::InvalidRequest, error.code());
+                assert_eq!("Invalid Request", error.message());
+            }
+            ResponseResult::Ok(_) => panic!("expected Err result"),
+        }
+    }
+
+    #[test]
+    fn never_answer_responses() {
+        let error = decode_error(
+            br#"{"jsonrpc":"2.0","id":7,"result":1,"error":{"code":1,"message":"oops"}}"#,
+        );
+        assert_eq!(None, error.id());
+        assert_eq!(Some(&RequestId::Number(7.into())), error.response_id());
+        assert_eq!(None, error.to_response());
+
+        // Without a method, an id isn't echoed even when the rest isn't response-like.
+        let error = decode_error(br#"{"jsonrpc":"2.0","id":7}"#);
+        assert_eq!(None, error.id());
+        assert_eq!(&RequestId::Null, error.to_response().unwrap().id());
+    }
+
+    #[test]
+    fn ignore_invalid_id() {
+        let error = decode_error(br#"{"jsonrpc":"2.0","method":"subtract","id":[1]}"#);
+        assert_eq!(None, error.id());
+        assert_eq!(&RequestId::Null, error.to_response().unwrap().id());
+    }
+
+    #[test]
+    fn parse_error_response() {
+        let response = decode_error(br#"{"jsonrpc":"2.0","id":1,"#)
+            .to_response()
+            .unwrap();
+        assert_eq!(&RequestId::Null, response.id());
+        match response.result() {
+            ResponseResult::Err(error) => {
+                assert_eq!(ErrorCode::ParseError, error.code());
+                assert_eq!("Parse error", error.message());
+            }
+            ResponseResult::Ok(_) => panic!("expected Err result"),
+        }
+    }
+}
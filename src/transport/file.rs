@@ -0,0 +1,252 @@
+//! JSON Lines files as a message source and sink, for replaying recorded traffic offline.
+//!
+//! Each line holds exactly one message. The reader and writer use blocking I/O, which is fine
+//! for local files but means they shouldn't be pointed at pipes or sockets on an async runtime.
+
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+
+use super::{Message, SerializeOptions};
+
+/// Reads one [`Message`] per line. Blank lines are skipped.
+#[derive(Debug)]
+pub struct JsonLinesReader<R> {
+    reader: R,
+    line: String,
+    line_number: usize,
+}
+
+impl JsonLinesReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> JsonLinesReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            line_number: 0,
+        }
+    }
+
+    fn read_message(&mut self) -> Option<Result<Message, Error>> {
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => self.line_number += 1,
+                Err(err) => return Some(Err(Error::Io(err))),
+            }
+
+            if self.line.trim().is_empty() {
+                continue;
+            }
+
+            return Some(
+                serde_json::from_str(&self.line).map_err(|source| Error::Decode {
+                    line: self.line_number,
+                    source,
+                }),
+            );
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for JsonLinesReader<R> {
+    type Item = Result<Message, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_message()
+    }
+}
+
+impl<R: BufRead + Unpin> Stream for JsonLinesReader<R> {
+    type Item = Result<Message, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.get_mut().read_message())
+    }
+}
+
+/// Writes one [`Message`] per line.
+#[derive(Debug)]
+pub struct JsonLinesWriter<W: Write> {
+    writer: BufWriter<W>,
+}
+
+impl JsonLinesWriter<File> {
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: BufWriter::new(writer),
+        }
+    }
+
+    pub fn write(&mut self, message: &Message) -> Result<(), Error> {
+        SerializeOptions::compact()
+            .to_writer(&mut self.writer, message)
+            .map_err(Error::Encode)?;
+        self.writer.write_all(b"\n").map_err(Error::Io)
+    }
+
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush().map_err(Error::Io)
+    }
+
+    pub fn into_inner(self) -> Result<W, Error> {
+        self.writer
+            .into_inner()
+            .map_err(|err| Error::Io(err.into_error()))
+    }
+}
+
+impl<W: Write + Unpin> Sink<Message> for JsonLinesWriter<W> {
+    type Error = Error;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+        self.get_mut().write(&item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(self.get_mut().flush())
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.poll_flush(cx)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// A line couldn't be decoded as a message. Line numbers start at 1.
+    Decode {
+        line: usize,
+        source: serde_json::Error,
+    },
+    Encode(serde_json::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::Decode { line, source } => {
+                write!(f, "invalid message on line {}: {}", line, source)
+            }
+            Error::Encode(err) => write!(f, "failed to encode message: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Decode { source, .. } => Some(source),
+            Error::Encode(err) => Some(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{notification::Notification, request::Request, request::RequestId};
+    use std::task::Waker;
+
+    const RECORDING: &str = r#"{"jsonrpc":"2.0","method":"subtract","params":[42,23],"id":1}
+
+{"jsonrpc":"2.0","method":"update","params":[1,2,3]}
+"#;
+
+    #[test]
+    fn read_messages() {
+        let messages: Vec<_> = JsonLinesReader::new(RECORDING.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(2, messages.len());
+        assert!(matches!(messages[0], Message::Request(_)));
+        assert!(matches!(messages[1], Message::Notification(_)));
+    }
+
+    #[test]
+    fn report_line_of_invalid_message() {
+        let input = "{\"jsonrpc\":\"2.0\",\"method\":\"update\"}\n\n{\"oops\":true}\n";
+        let mut reader = JsonLinesReader::new(input.as_bytes());
+
+        assert!(reader.next().unwrap().is_ok());
+        match reader.next().unwrap() {
+            Err(Error::Decode { line, .. }) => assert_eq!(3, line),
+            other => panic!("expected decode error, got {:?}", other),
+        }
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn poll_messages() {
+        let mut reader = JsonLinesReader::new(RECORDING.as_bytes());
+        let mut cx = Context::from_waker(Waker::noop());
+
+        assert!(matches!(
+            Pin::new(&mut reader).poll_next(&mut cx),
+            Poll::Ready(Some(Ok(Message::Request(_))))
+        ));
+        assert!(matches!(
+            Pin::new(&mut reader).poll_next(&mut cx),
+            Poll::Ready(Some(Ok(Message::Notification(_))))
+        ));
+        assert!(matches!(
+            Pin::new(&mut reader).poll_next(&mut cx),
+            Poll::Ready(None)
+        ));
+    }
+
+    #[test]
+    fn write_messages() {
+        let mut writer = JsonLinesWriter::new(Vec::new());
+        let mut cx = Context::from_waker(Waker::noop());
+
+        writer
+            .write(&Message::Request(Request::new(
+                "subtract",
+                Some(serde_json::json!([42, 23])),
+                RequestId::Number(1.into()),
+            )))
+            .unwrap();
+        Pin::new(&mut writer)
+            .start_send(Message::Notification(Notification::new(
+                "update",
+                Some(serde_json::json!([1, 2, 3])),
+            )))
+            .unwrap();
+        assert!(matches!(
+            Pin::new(&mut writer).poll_close(&mut cx),
+            Poll::Ready(Ok(()))
+        ));
+
+        let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert_eq!(RECORDING.replace("\n\n", "\n"), output);
+    }
+}
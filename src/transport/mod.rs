@@ -1,10 +1,19 @@
-use serde::{
-    de::{self, IntoDeserializer},
-    Deserialize, Serialize,
-};
+use serde::{de, Deserialize, Serialize};
 
 use crate::{notification::Notification, request::Request, response::Response};
 
+mod batch;
+pub mod codec;
+mod decode;
+pub mod file;
+mod serialize;
+mod warnings;
+
+pub use batch::{BatchBuilder, BatchEntry, BatchError, BatchRequest};
+pub use decode::{DecodeError, DecodeErrorKind, Decoder, InvalidUnicode, NonFiniteNumbers};
+pub use serialize::SerializeOptions;
+pub use warnings::{ProtocolWarning, ProtocolWarningKind, ProtocolWarnings};
+
 /// All the different types of message defined by the JSON-RPC 2.0 specification.
 /// Any individual message sent or received over a transport layer will be one of these types.
 #[derive(Debug)]
@@ -12,7 +21,8 @@ pub enum Message {
     Request(Request),
     Response(Response),
     Notification(Notification),
-    BatchRequest(Vec<Request>),
+    BatchRequest(BatchRequest),
+    BatchResponse(Vec<Response>),
 }
 
 impl Serialize for Message {
@@ -24,7 +34,8 @@ impl Serialize for Message {
             Message::Request(req) => req.serialize(serializer),
             Message::Response(resp) => resp.serialize(serializer),
             Message::Notification(notif) => notif.serialize(serializer),
-            Message::BatchRequest(reqs) => reqs.serialize(serializer),
+            Message::BatchRequest(batch) => batch.serialize(serializer),
+            Message::BatchResponse(responses) => responses.serialize(serializer),
         }
     }
 }
@@ -34,30 +45,56 @@ impl<'de> Deserialize<'de> for Message {
     where
         D: serde::Deserializer<'de>,
     {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        Message::from_value(&value).map_err(de::Error::custom)
+    }
+}
+
+impl Message {
+    fn from_value(value: &serde_json::Value) -> Result<Self, DecodeError> {
         // This is based on the implementation of untagged enum deserialization in the serde
         // derive implementation. serde uses a private enum called Content as its intermediate
         // representation. We use serde_json::Value instead which _might_ not be correct if we
         // were handling data types other than JSON, but since argonic is a JSON-RPC library, it
         // should be fine.
-        let value = serde_json::Value::deserialize(deserializer)?;
-        let deserializer = serde_json::Value::into_deserializer(value);
-
-        if let Ok(value) = Notification::deserialize(&deserializer) {
+        if let serde_json::Value::Array(values) = value {
+            return Message::from_array(values);
+        }
+        if let Ok(value) = Notification::deserialize(value) {
             return Ok(Message::Notification(value));
         }
-        if let Ok(value) = Request::deserialize(&deserializer) {
+        if let Ok(value) = Request::deserialize(value) {
             return Ok(Message::Request(value));
         }
-        if let Ok(value) = Response::deserialize(&deserializer) {
+        if let Ok(value) = Response::deserialize(value) {
             return Ok(Message::Response(value));
         }
-        if let Ok(value) = Vec::<Request>::deserialize(&deserializer) {
-            return Ok(Message::BatchRequest(value));
-        }
 
-        Err(de::Error::custom(
-            "data did not match any variant of Message",
-        ))
+        // Unlike serde's untagged enums, we don't want to throw away all of the errors, so
+        // pick the one from the variant the value was most likely meant to be.
+        Err(DecodeError::invalid_message(value))
+    }
+
+    fn from_array(values: &[serde_json::Value]) -> Result<Self, DecodeError> {
+        // Going by what most of the entries look like means a single malformed response doesn't
+        // turn a batch of responses into a batch of invalid requests, which would get answered.
+        let responses = values
+            .iter()
+            .filter(|value| decode::looks_like_response(value))
+            .count();
+        if responses * 2 > values.len() {
+            return values
+                .iter()
+                .map(|value| {
+                    Response::deserialize(value)
+                        .map_err(|_| DecodeError::invalid_batch_response(value))
+                })
+                .collect::<Result<_, _>>()
+                .map(Message::BatchResponse);
+        }
+        // Anything else is a batch of requests, which is also where an empty batch gets
+        // rejected.
+        BatchRequest::from_values(values).map(Message::BatchRequest)
     }
 }
 
@@ -127,11 +164,28 @@ mod tests {
         ]);
 
         match serde_json::from_value::<Message>(json).unwrap() {
-            Message::BatchRequest(requests) => assert_eq!(requests.len(), 2),
+            Message::BatchRequest(batch) => assert_eq!(batch.len(), 2),
             _ => panic!("expected BatchRequest variant"),
         }
     }
 
+    #[test]
+    fn deserialize_batch_response() {
+        let json = json!([
+            {"jsonrpc": "2.0", "result": 7, "id": "1"},
+            {"jsonrpc": "2.0", "error": {"code": -32600, "message": "Invalid Request", "data": "oops"}, "id": null}
+        ]);
+
+        match serde_json::from_value::<Message>(json.clone()).unwrap() {
+            Message::BatchResponse(responses) => {
+                assert_eq!(2, responses.len());
+                let message = Message::BatchResponse(responses);
+                assert_eq!(json, serde_json::to_value(message).unwrap());
+            }
+            _ => panic!("expected BatchResponse variant"),
+        }
+    }
+
     #[test]
     fn serialize_request() {
         let json = json!({
@@ -189,8 +243,8 @@ mod tests {
             }
         ]);
 
-        let requests: Vec<Request> = serde_json::from_value(json.clone()).unwrap();
-        let message = Message::BatchRequest(requests);
+        let batch: BatchRequest = serde_json::from_value(json.clone()).unwrap();
+        let message = Message::BatchRequest(batch);
         assert_eq!(json, serde_json::to_value(message).unwrap());
     }
 }
@@ -0,0 +1,173 @@
+use std::io;
+
+use serde::{ser::SerializeMap, Serialize};
+use serde_json::Value;
+
+/// Controls how outgoing messages are rendered to JSON text.
+///
+/// The default is compact output with fields in the order argonic emits them. Pretty output would
+/// split a message across lines, so framings that put one message on each line, like
+/// [`NewlineCodec`](super::codec::NewlineCodec) and
+/// [`JsonLinesWriter`](super::file::JsonLinesWriter), always render compactly.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SerializeOptions {
+    pretty: bool,
+    sort_keys: bool,
+}
+
+impl SerializeOptions {
+    pub fn compact() -> Self {
+        Self::default()
+    }
+
+    pub fn pretty() -> Self {
+        Self {
+            pretty: true,
+            ..Self::default()
+        }
+    }
+
+    /// Emit the keys of every object, including those nested inside params and results, in
+    /// lexicographic order so the same message always renders to the same bytes.
+    pub fn with_sorted_keys(mut self, sort_keys: bool) -> Self {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    /// The same options, but compact.
+    pub(crate) fn single_line(self) -> Self {
+        Self {
+            pretty: false,
+            ..self
+        }
+    }
+
+    pub fn to_writer<W, T>(&self, writer: W, message: &T) -> serde_json::Result<()>
+    where
+        W: io::Write,
+        T: Serialize + ?Sized,
+    {
+        if self.sort_keys {
+            // Going through Value is the simplest way to see every key of the message, and
+            // sorting it ourselves means we don't depend on whether serde_json's
+            // preserve_order feature has been enabled somewhere in the dependency graph.
+            let value = serde_json::to_value(message)?;
+            self.write(writer, &SortedValue(&value))
+        } else {
+            self.write(writer, message)
+        }
+    }
+
+    pub fn to_vec<T>(&self, message: &T) -> serde_json::Result<Vec<u8>>
+    where
+        T: Serialize + ?Sized,
+    {
+        let mut buffer = Vec::with_capacity(128);
+        self.to_writer(&mut buffer, message)?;
+        Ok(buffer)
+    }
+
+    pub fn to_string<T>(&self, message: &T) -> serde_json::Result<String>
+    where
+        T: Serialize + ?Sized,
+    {
+        let buffer = self.to_vec(message)?;
+        // serde_json only ever writes valid UTF-8.
+        Ok(String::from_utf8(buffer).expect("serde_json produced invalid UTF-8"))
+    }
+
+    fn write<W, T>(&self, writer: W, message: &T) -> serde_json::Result<()>
+    where
+        W: io::Write,
+        T: Serialize + ?Sized,
+    {
+        if self.pretty {
+            message.serialize(&mut serde_json::Serializer::pretty(writer))
+        } else {
+            message.serialize(&mut serde_json::Serializer::new(writer))
+        }
+    }
+}
+
+/// Serializes a [`Value`] with the keys of every object in sorted order.
+struct SortedValue<'a>(&'a Value);
+
+impl Serialize for SortedValue<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self.0 {
+            Value::Array(values) => serializer.collect_seq(values.iter().map(SortedValue)),
+            Value::Object(fields) => {
+                let mut fields: Vec<_> = fields.iter().collect();
+                fields.sort_unstable_by_key(|(key, _)| *key);
+
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+                for (key, value) in fields {
+                    map.serialize_entry(key, &SortedValue(value))?;
+                }
+                map.end()
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{request::Request, transport::Message};
+    use serde_json::json;
+
+    fn message() -> Message {
+        let request: Request = serde_json::from_value(json!({
+            "jsonrpc": "2.0",
+            "method": "search",
+            "params": {"query": "argonic", "limit": 10},
+            "id": 1
+        }))
+        .unwrap();
+        Message::Request(request)
+    }
+
+    #[test]
+    fn compact_output() {
+        assert_eq!(
+            r#"{"jsonrpc":"2.0","method":"search","params":{"limit":10,"query":"argonic"},"id":1}"#,
+            SerializeOptions::compact().to_string(&message()).unwrap()
+        );
+    }
+
+    #[test]
+    fn pretty_output() {
+        let output = SerializeOptions::pretty().to_string(&message()).unwrap();
+        assert!(output.contains('\n'));
+        assert_eq!(
+            serde_json::to_value(message()).unwrap(),
+            serde_json::from_str::<Value>(&output).unwrap()
+        );
+    }
+
+    #[test]
+    fn sorted_keys() {
+        assert_eq!(
+            r#"{"id":1,"jsonrpc":"2.0","method":"search","params":{"limit":10,"query":"argonic"}}"#,
+            SerializeOptions::compact()
+                .with_sorted_keys(true)
+                .to_string(&message())
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn sorted_keys_in_arrays() {
+        assert_eq!(
+            r#"[{"a":1,"b":2}]"#,
+            SerializeOptions::compact()
+                .with_sorted_keys(true)
+                .to_string(&json!([{"b": 2, "a": 1}]))
+                .unwrap()
+        );
+    }
+}
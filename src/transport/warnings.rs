@@ -0,0 +1,162 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use super::{BatchEntry, Message};
+use crate::request::RequestId;
+
+/// A deviation from the spec that a [`Decoder`](super::Decoder) tolerated rather than rejecting
+/// the message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProtocolWarning {
+    /// `NaN` or `Infinity` literals were replaced, as configured with
+    /// [`NonFiniteNumbers`](super::NonFiniteNumbers).
+    NonFiniteNumbers,
+    /// Invalid UTF-8 or lone surrogates were replaced, as configured with
+    /// [`InvalidUnicode`](super::InvalidUnicode).
+    InvalidUnicode,
+    /// A numeric id with a fractional part, which the spec says ids should not have.
+    FractionalId(RequestId),
+    /// More than one request in the same batch used this id, so their responses can't be told
+    /// apart.
+    DuplicateId(RequestId),
+}
+
+impl ProtocolWarning {
+    pub fn kind(&self) -> ProtocolWarningKind {
+        match self {
+            ProtocolWarning::NonFiniteNumbers => ProtocolWarningKind::NonFiniteNumbers,
+            ProtocolWarning::InvalidUnicode => ProtocolWarningKind::InvalidUnicode,
+            ProtocolWarning::FractionalId(_) => ProtocolWarningKind::FractionalId,
+            ProtocolWarning::DuplicateId(_) => ProtocolWarningKind::DuplicateId,
+        }
+    }
+}
+
+impl fmt::Display for ProtocolWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolWarning::NonFiniteNumbers => f.write_str("replaced non-finite numbers"),
+            ProtocolWarning::InvalidUnicode => f.write_str("replaced invalid unicode"),
+            ProtocolWarning::FractionalId(id) => write!(f, "fractional id {}", id),
+            ProtocolWarning::DuplicateId(id) => write!(f, "duplicate id {} in batch", id),
+        }
+    }
+}
+
+/// The kinds of [`ProtocolWarning`], for looking up counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProtocolWarningKind {
+    NonFiniteNumbers,
+    InvalidUnicode,
+    FractionalId,
+    DuplicateId,
+}
+
+impl ProtocolWarningKind {
+    const COUNT: usize = ProtocolWarningKind::DuplicateId as usize + 1;
+}
+
+type Callback = Box<dyn Fn(&ProtocolWarning) + Send + Sync>;
+
+/// Collects the [`ProtocolWarning`]s from one or more decoders, counting them by kind and
+/// optionally passing each one to a callback, so interop problems show up somewhere instead of
+/// being silently accepted.
+///
+/// Share one between decoders by wrapping it in an `Arc`.
+#[derive(Default)]
+pub struct ProtocolWarnings {
+    counts: [AtomicU64; ProtocolWarningKind::COUNT],
+    callback: Option<Callback>,
+}
+
+impl ProtocolWarnings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls `callback` with every warning, for logging them or feeding them into metrics.
+    pub fn with_callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&ProtocolWarning) + Send + Sync + 'static,
+    {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    pub fn count(&self, kind: ProtocolWarningKind) -> u64 {
+        self.counts[kind as usize].load(Ordering::Relaxed)
+    }
+
+    /// The number of warnings of every kind put together.
+    pub fn total(&self) -> u64 {
+        self.counts
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    pub(super) fn report(&self, warning: ProtocolWarning) {
+        self.counts[warning.kind() as usize].fetch_add(1, Ordering::Relaxed);
+        if let Some(callback) = &self.callback {
+            callback(&warning);
+        }
+    }
+
+    /// Reports the deviations that can only be seen once the message has been decoded.
+    pub(super) fn check(&self, message: &Message) {
+        let check_id = |id: &RequestId| {
+            if let RequestId::Number(number) = id {
+                if number.as_f64().is_some_and(|number| number.fract() != 0.0) {
+                    self.report(ProtocolWarning::FractionalId(id.clone()));
+                }
+            }
+        };
+
+        match message {
+            Message::Request(request) => check_id(request.id()),
+            Message::Response(response) => check_id(response.id()),
+            Message::Notification(_) => {}
+            Message::BatchRequest(batch) => {
+                let mut ids = HashSet::new();
+                for id in batch.entries().iter().filter_map(BatchEntry::id) {
+                    check_id(id);
+                    if !ids.insert(id) {
+                        self.report(ProtocolWarning::DuplicateId(id.clone()));
+                    }
+                }
+            }
+            Message::BatchResponse(responses) => responses
+                .iter()
+                .for_each(|response| check_id(response.id())),
+        }
+    }
+}
+
+impl fmt::Debug for ProtocolWarnings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProtocolWarnings")
+            .field("counts", &self.counts)
+            .field("callback", &self.callback.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_every_kind() {
+        use ProtocolWarningKind::*;
+        for kind in [NonFiniteNumbers, InvalidUnicode, FractionalId, DuplicateId] {
+            // A new kind breaks this match, so it gets added to the list above and to COUNT.
+            match kind {
+                NonFiniteNumbers | InvalidUnicode | FractionalId | DuplicateId => {}
+            }
+            assert!((kind as usize) < ProtocolWarningKind::COUNT);
+        }
+    }
+}
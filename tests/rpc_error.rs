@@ -0,0 +1,66 @@
+#![cfg(feature = "macros")]
+
+use argonic::response::{ErrorCode, ResponseError, RpcError};
+use serde_json::json;
+
+#[derive(Debug, RpcError)]
+enum AccountError {
+    #[rpc_error(code = -32010, message = "quota exceeded")]
+    QuotaExceeded,
+    #[rpc_error(code = 404, message = "no account named {0}")]
+    NotFound(String),
+    #[rpc_error(code = 422, message = "invalid {field}")]
+    Invalid {
+        field: String,
+        #[rpc_error(data)]
+        reasons: Vec<String>,
+    },
+    #[rpc_error(code = 409, message = "{0} conflicts with {1:?}")]
+    Conflict(u32, #[rpc_error(data)] u32),
+}
+
+#[test]
+fn unit_variant() {
+    let error = ResponseError::from(AccountError::QuotaExceeded);
+    assert_eq!(ErrorCode::ServerError(-32010), error.code());
+    assert_eq!("quota exceeded", error.message());
+    assert_eq!(None, error.data());
+}
+
+#[test]
+fn tuple_variant() {
+    let error: ResponseError = AccountError::NotFound("alice".to_string()).into();
+    assert_eq!(ErrorCode::ApplicationError(404), error.code());
+    assert_eq!("no account named alice", error.message());
+}
+
+#[test]
+fn struct_variant_with_data() {
+    let error: ResponseError = AccountError::Invalid {
+        field: "email".to_string(),
+        reasons: vec!["missing @".to_string()],
+    }
+    .into();
+    assert_eq!(ErrorCode::ApplicationError(422), error.code());
+    assert_eq!("invalid email", error.message());
+    assert_eq!(Some(&json!(["missing @"])), error.data());
+}
+
+#[test]
+fn positional_data_field() {
+    let error: ResponseError = AccountError::Conflict(1, 2).into();
+    assert_eq!("1 conflicts with 2", error.message());
+    assert_eq!(Some(&json!(2)), error.data());
+}
+
+#[test]
+fn generic_enum() {
+    #[derive(RpcError)]
+    enum Wrapped<T: std::fmt::Display> {
+        #[rpc_error(code = 1, message = "wrapped: {0}")]
+        Inner(T),
+    }
+
+    let error: ResponseError = Wrapped::Inner(42).into();
+    assert_eq!("wrapped: 42", error.message());
+}